@@ -0,0 +1,300 @@
+use crate::{
+    error::BeanCountError,
+    inventory::{Inventory, Position},
+    models::{sort_directives, Account, Amount, Directive, Flag, Transaction, TransactionLine},
+    to_file::ToBeancountFile,
+};
+use bigdecimal::{BigDecimal, Signed, Zero};
+use indexmap::IndexMap;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// a transaction line whose amount is known, missing amount is filled by interpolation
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Posting {
+    pub flag: Flag,
+    pub account: Account,
+    pub units: Amount,
+    /// cost per unit
+    pub cost: Option<Amount>,
+    /// price per unit
+    pub price: Option<Amount>,
+    /// amount used to balance the transaction
+    pub weight: Amount,
+}
+
+impl Posting {
+    fn from_line(line: &TransactionLine, units: Amount) -> Self {
+        let cost = line.cost.as_ref().map(|(cost, _)| cost.clone());
+        let price = match (&line.single_price, &line.total_price) {
+            (Some(price), _) => Some(price.clone()),
+            (None, Some((total, currency))) if !units.0.is_zero() => {
+                Some((total / units.0.abs(), currency.clone()))
+            }
+            _ => None,
+        };
+        let weight = match (&cost, &line.single_price, &line.total_price) {
+            (Some((number, currency)), _, _) | (None, Some((number, currency)), _) => {
+                (&units.0 * number, currency.clone())
+            }
+            (None, None, Some((total, currency))) => {
+                let total = if units.0.is_negative() {
+                    -total
+                } else {
+                    total.clone()
+                };
+                (total, currency.clone())
+            }
+            (None, None, None) => units.clone(),
+        };
+        Posting {
+            flag: line.flag,
+            account: line.account.clone(),
+            units,
+            cost,
+            price,
+            weight,
+        }
+    }
+
+    pub fn position(&self) -> Position {
+        Position::new(self.units.clone(), self.cost.clone())
+    }
+}
+
+/// resolve postings of transaction, the only line without amount receives the residual
+/// ```rust
+/// use beancount::{booking::book, models::Directive, parser::DirectiveExpressionParser};
+/// use beancount::models::AccountNames;
+/// use bigdecimal::BigDecimal;
+/// let directive = DirectiveExpressionParser::new()
+///     .parse(&AccountNames::default(), "1970-01-01 * \"Narration\"\n  Assets:Bank -10 USD\n  Expenses:Food")
+///     .unwrap();
+/// if let Directive::Transaction(transaction) = directive {
+///     let postings = book(&transaction).unwrap();
+///     assert_eq!((BigDecimal::from(10), "USD".to_owned()), postings[1].units);
+/// }
+/// ```
+pub fn book(transaction: &Transaction) -> Result<Vec<Posting>, BeanCountError> {
+    let mut residual: IndexMap<String, BigDecimal> = IndexMap::new();
+    let mut postings = vec![];
+    let mut missing = None;
+    for (index, line) in transaction.lines.iter().enumerate() {
+        match &line.amount {
+            Some(amount) => {
+                let posting = Posting::from_line(line, amount.clone());
+                *residual.entry(posting.weight.1.clone()).or_default() += &posting.weight.0;
+                postings.push(posting);
+            }
+            None if missing.is_none() => missing = Some(index),
+            None => return Err(BeanCountError::AmbiguousPosting(transaction.date)),
+        }
+    }
+    if let Some(index) = missing {
+        let line = &transaction.lines[index];
+        let filled = residual
+            .into_iter()
+            .filter(|(_, number)| !number.is_zero())
+            .map(|(currency, number)| Posting::from_line(line, (-number, currency)));
+        let tail = postings.split_off(index);
+        postings.extend(filled);
+        postings.extend(tail);
+    }
+    Ok(postings)
+}
+
+/// insert padding transactions for `pad` directives, using the following `balance` of the account
+pub fn pad(directives: &[Directive]) -> Result<Vec<Directive>, BeanCountError> {
+    let mut directives = directives.to_vec();
+    sort_directives(&mut directives);
+
+    let mut balances: HashMap<Account, Inventory> = HashMap::new();
+    // padded account -> (index of pad directive, source account, padded currencies)
+    let mut active_pads: HashMap<Account, (usize, Account, Vec<String>)> = HashMap::new();
+    let mut insertions: HashMap<usize, Vec<Directive>> = HashMap::new();
+
+    for (index, directive) in directives.iter().enumerate() {
+        match directive {
+            Directive::Transaction(transaction) => {
+                for posting in book(transaction)? {
+                    balances
+                        .entry(posting.account)
+                        .or_default()
+                        .add_amount(posting.units);
+                }
+            }
+            Directive::Pad { from, to, .. } => {
+                active_pads.insert(from.clone(), (index, to.clone(), vec![]));
+            }
+            Directive::Balance {
+                account, amount, ..
+            } => {
+                let (pad_index, source, padded) = match active_pads.get_mut(account) {
+                    Some(pad) if !pad.2.contains(&amount.1) => pad,
+                    _ => continue,
+                };
+                let current: BigDecimal = balances
+                    .iter()
+                    .filter(|(child, _)| account.contains(child))
+                    .map(|(_, inventory)| inventory.units_of(&amount.1))
+                    .sum();
+                let difference = (&amount.0 - current, amount.1.clone());
+                padded.push(amount.1.clone());
+                if difference.0.is_zero() {
+                    continue;
+                }
+                let pad_date = *directives[*pad_index].date().unwrap();
+                let transaction = Transaction::new(
+                    pad_date,
+                    Flag::Padding,
+                    None,
+                    Some(format!(
+                        "(Padding inserted for Balance of {} for difference {})",
+                        amount.to_text(),
+                        difference.to_text()
+                    )),
+                    vec![],
+                    vec![],
+                    vec![
+                        padding_line(account, difference.clone()),
+                        padding_line(source, (-&difference.0, difference.1.clone())),
+                    ],
+                );
+                balances
+                    .entry(account.clone())
+                    .or_default()
+                    .add_amount(difference.clone());
+                balances
+                    .entry(source.clone())
+                    .or_default()
+                    .add_amount((-difference.0, difference.1));
+                insertions
+                    .entry(*pad_index)
+                    .or_default()
+                    .push(Directive::Transaction(transaction));
+            }
+            _ => {}
+        }
+    }
+
+    let mut padded = Vec::with_capacity(directives.len() + insertions.len());
+    for (index, directive) in directives.into_iter().enumerate() {
+        padded.push(directive);
+        if let Some(transactions) = insertions.remove(&index) {
+            padded.extend(transactions);
+        }
+    }
+    Ok(padded)
+}
+
+fn padding_line(account: &Account, amount: Amount) -> TransactionLine {
+    TransactionLine {
+        flag: Flag::Complete,
+        account: account.clone(),
+        amount: Some(amount),
+        cost: None,
+        single_price: None,
+        total_price: None,
+        comment: None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        booking::{book, pad},
+        error::BeanCountError,
+        loader::load,
+        models::{Directive, Flag},
+    };
+    use bigdecimal::BigDecimal;
+    use std::str::FromStr;
+
+    fn transaction(content: &str) -> crate::models::Transaction {
+        match load(content).unwrap().directives.remove(0) {
+            Directive::Transaction(transaction) => transaction,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn interpolate_with_cost_and_price() {
+        let transaction = transaction(
+            r#"2020-01-01 * "Buy"
+  Assets:Broker 10 HOOL {500 USD}
+  Expenses:Fee 5 USD
+  Assets:Cash
+"#,
+        );
+        let postings = book(&transaction).unwrap();
+        assert_eq!(3, postings.len());
+        assert_eq!(
+            (BigDecimal::from(-5005), "USD".to_owned()),
+            postings[2].units
+        );
+        assert_eq!(
+            Some((BigDecimal::from(500), "USD".to_owned())),
+            postings[0].cost
+        );
+    }
+
+    #[test]
+    fn total_price_weight() {
+        let transaction = transaction(
+            r#"2020-01-01 * "Exchange"
+  Assets:USD -3 USD @@ 20 CNY
+  Assets:CNY
+"#,
+        );
+        let postings = book(&transaction).unwrap();
+        assert_eq!(
+            (BigDecimal::from(-20), "CNY".to_owned()),
+            postings[0].weight
+        );
+        assert_eq!((BigDecimal::from(20), "CNY".to_owned()), postings[1].units);
+    }
+
+    #[test]
+    fn more_than_one_missing_amount() {
+        let transaction = transaction(
+            r#"2020-01-01 * "Broken"
+  Assets:Cash 10 USD
+  Expenses:Food
+  Expenses:Drink
+"#,
+        );
+        assert_eq!(
+            Err(BeanCountError::AmbiguousPosting(transaction.date)),
+            book(&transaction)
+        );
+    }
+
+    #[test]
+    fn insert_padding() {
+        let ledger = load(
+            r#"2020-01-01 open Assets:Bank
+2020-01-01 open Equity:Opening-Balances
+2020-01-01 pad Assets:Bank Equity:Opening-Balances
+2020-01-03 * "Coffee"
+  Assets:Bank -5 USD
+  Expenses:Coffee
+2020-01-05 balance Assets:Bank 95 USD
+"#,
+        )
+        .unwrap();
+        let directives = pad(&ledger.directives).unwrap();
+        assert_eq!(6, directives.len());
+        match &directives[3] {
+            Directive::Transaction(transaction) => {
+                assert_eq!(Flag::Padding, transaction.flag);
+                let postings = book(transaction).unwrap();
+                assert_eq!(
+                    (BigDecimal::from_str("100").unwrap(), "USD".to_owned()),
+                    postings[0].units
+                );
+                assert_eq!("Equity:Opening-Balances", postings[1].account.to_string());
+            }
+            _ => panic!("padding transaction is not inserted"),
+        }
+    }
+}
@@ -0,0 +1,417 @@
+use crate::{
+    error::BeanCountError,
+    loader::{account_names, locate, syntax_error},
+    models::{AccountNames, Directive, Transaction, TransactionLine},
+    parser::DirectiveExpressionParser,
+    to_file::ToBeancountFile,
+};
+
+/// a piece of source file, the text of all items concatenated is the file itself
+#[derive(Debug, Clone, PartialEq)]
+pub enum Item {
+    /// a directive with the text it is parsed from, including postings and the line break
+    Directive {
+        text: String,
+        directive: Directive,
+    },
+    Blank(String),
+    /// a whole line comment, `;` or `///`
+    Comment(String),
+}
+
+impl Item {
+    pub fn text(&self) -> &str {
+        match self {
+            Item::Directive { text, .. } => text,
+            Item::Blank(text) | Item::Comment(text) => text,
+        }
+    }
+}
+
+/// concrete syntax of a file which keeps comments, blank lines, spacing and order
+///
+/// Untouched items keep their original text, so `to_text` gives back the parsed file byte for
+/// byte and edits only change the text of edited directives.
+/// ```rust
+/// use beancount::document::Document;
+/// let content = "; my ledger\n\n1970-01-01 open   Assets:Bank\n";
+/// let document = Document::parse(content).unwrap();
+/// assert_eq!(1, document.directives().count());
+/// assert_eq!(content, document.to_text());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    items: Vec<Item>,
+}
+
+impl Document {
+    pub fn parse(content: &str) -> Result<Document, BeanCountError> {
+        let names = account_names(content);
+        let mut chunker = Chunker::default();
+        let mut items = vec![];
+        let (mut offset, mut line_number) = (0, 1);
+        for line in content.split_inclusive('\n') {
+            for chunk in chunker.push(line) {
+                let text = chunk.text();
+                let (length, lines) = (text.len(), text.matches('\n').count());
+                items.push(chunk.into_item(&names, offset, line_number)?);
+                offset += length;
+                line_number += lines;
+            }
+        }
+        if let Some(chunk) = chunker.finish() {
+            items.push(chunk.into_item(&names, offset, line_number)?);
+        }
+        Ok(Document { items })
+    }
+
+    pub fn items(&self) -> &[Item] {
+        &self.items
+    }
+
+    pub fn directives(&self) -> impl Iterator<Item = &Directive> {
+        self.items.iter().filter_map(|item| match item {
+            Item::Directive { directive, .. } => Some(directive),
+            _ => None,
+        })
+    }
+
+    /// modify the directive at `index` of items, its text is rendered again only if it changed
+    ///
+    /// Of a transaction, only the changed header and postings are rendered again, other lines
+    /// like comments between postings keep their text.
+    pub fn edit(&mut self, index: usize, edit: impl FnOnce(&mut Directive)) {
+        if let Some(Item::Directive { text, directive }) = self.items.get_mut(index) {
+            let original = directive.clone();
+            edit(directive);
+            if &original == directive {
+                return;
+            }
+            let edited = match (&original, &*directive) {
+                (Directive::Transaction(before), Directive::Transaction(after)) => {
+                    edit_transaction(text, before, after)
+                }
+                _ => None,
+            };
+            *text = edited.unwrap_or_else(|| {
+                let line_break = if text.ends_with('\n') { "\n" } else { "" };
+                format!("{}{}", directive.to_text(), line_break)
+            });
+        }
+    }
+
+    /// insert directive as a new item at `index`
+    pub fn insert(&mut self, index: usize, directive: Directive) {
+        let text = format!("{}\n", directive.to_text());
+        self.items
+            .insert(index, Item::Directive { text, directive });
+    }
+
+    pub fn remove(&mut self, index: usize) -> Item {
+        self.items.remove(index)
+    }
+
+    pub fn to_text(&self) -> String {
+        self.items.iter().map(|item| item.text()).collect()
+    }
+}
+
+/// text of edited transaction keeping lines which did not change, `None` if the postings of
+/// `before` cannot be matched to lines of text
+fn edit_transaction(text: &str, before: &Transaction, after: &Transaction) -> Option<String> {
+    let is_comment = |line: &str| line.trim_start().starts_with(';');
+    let mut lines = text.split_inclusive('\n');
+    let header = lines.next()?;
+    let body: Vec<&str> = lines.collect();
+    if body.iter().filter(|line| !is_comment(line)).count() != before.lines.len() {
+        return None;
+    }
+
+    let mut edited = String::new();
+    let push_rendered = |edited: &mut String, rendered: String| {
+        if !edited.is_empty() && !edited.ends_with('\n') {
+            edited.push('\n');
+        }
+        edited.push_str(&rendered);
+        edited.push('\n');
+    };
+    let without_lines = |transaction: &Transaction| Transaction {
+        lines: vec![],
+        ..transaction.clone()
+    };
+    if without_lines(before) == without_lines(after) {
+        edited.push_str(header);
+    } else {
+        push_rendered(&mut edited, without_lines(after).to_text());
+    }
+    // postings of `before` kept in `after`, the others are replaced in place or removed
+    let kept = common_postings(&before.lines, &after.lines);
+    let mut next = 0;
+    let mut index = 0;
+    for line in body {
+        if is_comment(line) {
+            edited.push_str(line);
+            continue;
+        }
+        match kept[index] {
+            Some(position) => {
+                for posting in &after.lines[next..position] {
+                    push_rendered(&mut edited, format!("  {}", posting.to_text()));
+                }
+                edited.push_str(line);
+                next = position + 1;
+            }
+            None => {
+                let limit = kept[index..]
+                    .iter()
+                    .flatten()
+                    .next()
+                    .copied()
+                    .unwrap_or(after.lines.len());
+                if next < limit {
+                    let posting = &after.lines[next];
+                    push_rendered(&mut edited, format!("  {}", posting.to_text()));
+                    next += 1;
+                }
+            }
+        }
+        index += 1;
+    }
+    for posting in &after.lines[next..] {
+        push_rendered(&mut edited, format!("  {}", posting.to_text()));
+    }
+    if !text.ends_with('\n') && edited.ends_with('\n') {
+        edited.pop();
+    }
+    Some(edited)
+}
+
+/// index in `after` of each posting of `before` which is kept, by longest common subsequence
+fn common_postings(before: &[TransactionLine], after: &[TransactionLine]) -> Vec<Option<usize>> {
+    let mut lengths = vec![vec![0; after.len() + 1]; before.len() + 1];
+    for i in (0..before.len()).rev() {
+        for j in (0..after.len()).rev() {
+            lengths[i][j] = if before[i] == after[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+    let mut kept = vec![None; before.len()];
+    let (mut i, mut j) = (0, 0);
+    while i < before.len() && j < after.len() {
+        if before[i] == after[j] {
+            kept[i] = Some(j);
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    kept
+}
+
+/// a group of lines forming one item, not yet parsed
+#[derive(Debug, PartialEq)]
+pub(crate) enum Chunk {
+    Directive(String),
+    Blank(String),
+    Comment(String),
+}
+
+impl Chunk {
+    pub(crate) fn text(&self) -> &str {
+        match self {
+            Chunk::Directive(text) | Chunk::Blank(text) | Chunk::Comment(text) => text,
+        }
+    }
+
+    fn into_item(
+        self,
+        names: &AccountNames,
+        offset: usize,
+        line: usize,
+    ) -> Result<Item, BeanCountError> {
+        Ok(match self {
+            Chunk::Directive(text) => {
+                let directive = parse_chunk(&text, names, offset, line)?;
+                Item::Directive { text, directive }
+            }
+            Chunk::Blank(text) => Item::Blank(text),
+            Chunk::Comment(text) => Item::Comment(text),
+        })
+    }
+}
+
+/// groups lines into chunks, a directive continues while the following lines are indented
+#[derive(Debug, Default)]
+pub(crate) struct Chunker {
+    directive: Option<String>,
+}
+
+impl Chunker {
+    /// feed the next line including its line break, returns chunks completed by it
+    pub(crate) fn push(&mut self, line: &str) -> Vec<Chunk> {
+        let blank = line.trim().is_empty();
+        let indented = line.starts_with(' ') || line.starts_with('\t');
+        if let (Some(directive), true, false) = (&mut self.directive, indented, blank) {
+            directive.push_str(line);
+            return vec![];
+        }
+        let mut chunks: Vec<Chunk> = self.finish().into_iter().collect();
+        let trimmed = line.trim_start();
+        if blank {
+            chunks.push(Chunk::Blank(line.to_owned()));
+        } else if trimmed.starts_with(';') || trimmed.starts_with("//") {
+            chunks.push(Chunk::Comment(line.to_owned()));
+        } else {
+            self.directive = Some(line.to_owned());
+        }
+        chunks
+    }
+
+    pub(crate) fn finish(&mut self) -> Option<Chunk> {
+        self.directive.take().map(Chunk::Directive)
+    }
+}
+
+/// parse text of a directive chunk starting at byte `offset` and `line` of the file, comment
+/// lines between postings are skipped
+pub(crate) fn parse_chunk(
+    text: &str,
+    names: &AccountNames,
+    offset: usize,
+    line: usize,
+) -> Result<Directive, BeanCountError> {
+    let source: Vec<&str> = text
+        .lines()
+        .enumerate()
+        .filter(|(index, line)| *index == 0 || !line.trim_start().starts_with(';'))
+        .map(|(_, line)| line.trim_end_matches('\r'))
+        .collect();
+    let source = source.join("\n");
+    let mut directive = DirectiveExpressionParser::new()
+        .parse(names, source.trim_end())
+        .map_err(|e| match syntax_error(e) {
+            BeanCountError::SyntaxError(location) => BeanCountError::SyntaxError(offset + location),
+            error => error,
+        })?;
+    locate(std::slice::from_mut(&mut directive), &source, line);
+    Ok(directive)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        document::{Document, Item},
+        error::BeanCountError,
+        models::Directive,
+    };
+
+    const CONTENT: &str = "option \"title\" \"Home\"\r\n\
+                           /// accounts\n\
+                           2020-01-01 open Assets:Bank   USD\n\
+                           \n\
+                           \n\
+                           ; spending\n\
+                           2020-01-05 *   \"Shop\" \"Lunch\"\n\
+                           \x20   Assets:Bank     -20 USD\n\
+                           \x20 ; paid by card\n\
+                           \x20   Expenses:Food  ; rice\n\
+                           2020-01-06 close Assets:Bank";
+
+    #[test]
+    fn round_trip() {
+        let document = Document::parse(CONTENT).unwrap();
+        assert_eq!(CONTENT, document.to_text());
+        assert_eq!(4, document.directives().count());
+        assert_eq!(Item::Blank("\n".to_owned()), document.items()[3]);
+        assert_eq!(
+            Item::Comment("; spending\n".to_owned()),
+            document.items()[5]
+        );
+    }
+
+    #[test]
+    fn minimal_edit() {
+        let mut document = Document::parse(CONTENT).unwrap();
+        document.edit(6, |directive| {
+            if let Directive::Transaction(transaction) = directive {
+                transaction.tags.push("food".to_owned());
+            }
+        });
+        assert_eq!(
+            CONTENT.replace(
+                "2020-01-05 *   \"Shop\" \"Lunch\"\n",
+                "2020-01-05 * \"Shop\" \"Lunch\" #food\n"
+            ),
+            document.to_text()
+        );
+
+        document.edit(7, |directive| {
+            if let Directive::Close { date, .. } = directive {
+                *date = date.succ_opt().unwrap();
+            }
+        });
+        assert!(document
+            .to_text()
+            .ends_with("\n2020-01-07 close Assets:Bank"));
+    }
+
+    #[test]
+    fn edit_postings_keeps_comments() {
+        let mut document = Document::parse(CONTENT).unwrap();
+        document.edit(6, |directive| {
+            if let Directive::Transaction(transaction) = directive {
+                transaction.lines[1].comment = Some("noodles".to_owned());
+            }
+        });
+        assert_eq!(
+            CONTENT.replace(
+                "\x20   Expenses:Food  ; rice\n",
+                "\x20 Expenses:Food ; noodles\n"
+            ),
+            document.to_text()
+        );
+
+        document.edit(6, |directive| {
+            if let Directive::Transaction(transaction) = directive {
+                transaction.lines.remove(0);
+            }
+        });
+        assert!(document.to_text().contains(
+            "2020-01-05 *   \"Shop\" \"Lunch\"\n\x20 ; paid by card\n\x20 Expenses:Food ; noodles\n"
+        ));
+    }
+
+    #[test]
+    fn edit_with_custom_account_names() {
+        let content = "option \"name_assets\" \"Aktiva\"\n\
+                       2020-01-05 * \"Lunch\"\n\
+                       \x20 Aktiva:Bank  -20 USD\n\
+                       \x20 ; paid by card\n\
+                       \x20 Expenses:Food\n";
+        let mut document = Document::parse(content).unwrap();
+        document.edit(1, |directive| {
+            if let Directive::Transaction(transaction) = directive {
+                transaction.lines[0].amount.as_mut().unwrap().0 = (-25).into();
+                transaction.narration = Some("Dinner".to_owned());
+            }
+        });
+        let expected = content
+            .replace("\"Lunch\"", "\"Dinner\"")
+            .replace("Aktiva:Bank  -20 USD", "Aktiva:Bank -25 USD");
+        assert_eq!(expected, document.to_text());
+        assert!(Document::parse(&document.to_text()).is_ok());
+    }
+
+    #[test]
+    fn error_location() {
+        let error = Document::parse("1970-01-01 open Assets:Bank\n1970-01-01 opn Assets:Bank\n")
+            .unwrap_err();
+        assert_eq!(BeanCountError::SyntaxError(39), error);
+    }
+}
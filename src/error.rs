@@ -1,9 +1,43 @@
+use crate::models::Location;
+use chrono::NaiveDate;
 use thiserror::Error;
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Error)]
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
 pub enum BeanCountError {
     #[error("date is invalid")]
     InvalidDate,
     #[error("account is invalid")]
     InvalidAccount,
+    #[error("syntax error at {0}")]
+    SyntaxError(usize),
+    #[error("option {0} is unknown")]
+    UnknownOption(String),
+    #[error("value {1} of option {0} is invalid")]
+    InvalidOption(String, String),
+    #[error("transaction on {0} has more than one posting without amount")]
+    AmbiguousPosting(NaiveDate),
+    #[error("invalid query: {0}")]
+    InvalidQuery(String),
+    #[error("fail to read: {0}")]
+    Io(String),
+    #[error("plugin {0} is unknown")]
+    UnknownPlugin(String),
+    #[error("commodity {0} is used without commodity directive")]
+    UndeclaredCommodity(String),
+    #[error("config {1} of plugin {0} is invalid")]
+    InvalidPluginConfig(String, String),
+    #[error("account {1} has children but is posted to on {0} {2}")]
+    NonLeafAccount(NaiveDate, String, Location),
+    #[error("account {1} holds more than one commodity since {0} {2}")]
+    MultipleCommodities(NaiveDate, String, Location),
+    #[error("transaction {1} on {0} is a duplicate")]
+    DuplicateTransaction(NaiveDate, String),
+    #[error("posting {2} to {1} on {0} may be a duplicate")]
+    PossibleDuplicate(NaiveDate, String, String),
+    #[error("gains of sale on {0} differ from income postings by {1}")]
+    InvalidGains(NaiveDate, String),
+    #[error("budget on {0} is invalid, expect account, period and amount")]
+    InvalidBudget(NaiveDate),
+    #[error("recurrence rule of forecast {1} on {0} is invalid")]
+    InvalidRecurrence(NaiveDate, String),
 }
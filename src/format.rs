@@ -0,0 +1,155 @@
+use regex::Regex;
+
+/// widths used by `format`, missing values are computed from the content
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FormatConfig {
+    /// width of the account part before amounts
+    pub prefix_width: Option<usize>,
+    /// width of the number part of amounts
+    pub num_width: Option<usize>,
+    /// column at which currencies start, counted from zero, overrides `num_width`
+    pub currency_column: Option<usize>,
+}
+
+/// align numbers and currencies of postings and amount directives to common columns
+///
+/// Other lines without amount, like comments, headers of transactions and blank lines, are kept
+/// as they are, indentation of all postings is normalized to two spaces.
+/// ```rust
+/// use beancount::format::{format, FormatConfig};
+/// let content = "2020-01-01 * \"Lunch\"\n  Assets:Cash    -10.5 USD\n  Expenses:Food 10.5 USD ; lunch\n";
+/// assert_eq!(
+///     "2020-01-01 * \"Lunch\"\n  Assets:Cash    -10.5 USD\n  Expenses:Food   10.5 USD ; lunch\n",
+///     format(content, &FormatConfig::default())
+/// );
+/// ```
+pub fn format(content: &str, config: &FormatConfig) -> String {
+    let regex = Regex::new(
+        r#"^([^";]*?)\s+([-+]?\s*[\d,]+(?:\.\d*)?)\s+([A-Z][A-Z0-9'._-]{0,22}[A-Z0-9]\b.*)$"#,
+    )
+    .expect("regex of amount line should be valid");
+
+    let lines: Vec<(String, Option<(String, String)>)> = content
+        .lines()
+        .map(|line| match regex.captures(line) {
+            Some(captures) => {
+                let prefix = normalize_indent(&captures[1]);
+                let number: String = captures[2].split_whitespace().collect();
+                (prefix, Some((number, captures[3].to_owned())))
+            }
+            None if is_posting(line) => (normalize_indent(line), None),
+            None => (line.to_owned(), None),
+        })
+        .collect();
+
+    let amounts = lines.iter().filter_map(|(prefix, amount)| {
+        amount
+            .as_ref()
+            .map(|(number, _)| (prefix.chars().count(), number.chars().count()))
+    });
+    let (max_prefix, max_number) = amounts.fold((0, 0), |(prefix, number), (p, n)| {
+        (prefix.max(p), number.max(n))
+    });
+    let prefix_width = config.prefix_width.unwrap_or(max_prefix).max(max_prefix);
+    let num_width = match config.currency_column {
+        Some(column) => column.saturating_sub(prefix_width + 3).max(max_number),
+        None => config.num_width.unwrap_or(max_number).max(max_number),
+    };
+
+    let mut formatted: String = lines
+        .iter()
+        .map(|(prefix, amount)| match amount {
+            Some((number, rest)) => format!(
+                "{:<prefix_width$}  {:>num_width$} {}",
+                prefix,
+                number,
+                rest,
+                prefix_width = prefix_width,
+                num_width = num_width
+            ),
+            None => prefix.clone(),
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+    if content.ends_with('\n') {
+        formatted.push('\n');
+    }
+    formatted
+}
+
+/// indented line starting with an account, optionally after a flag
+fn is_posting(line: &str) -> bool {
+    if !line.starts_with(char::is_whitespace) {
+        return false;
+    }
+    let mut tokens = line.split_whitespace();
+    let account = match tokens.next() {
+        Some(flag) if flag.chars().count() == 1 => tokens.next(),
+        token => token,
+    };
+    account.is_some_and(|account| {
+        !account.starts_with(';') && account.contains(':') && !account.ends_with(':')
+    })
+}
+
+fn normalize_indent(prefix: &str) -> String {
+    let trimmed = prefix.trim_start();
+    if trimmed.len() == prefix.len() {
+        trimmed.to_owned()
+    } else {
+        format!("  {}", trimmed)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::format::{format, FormatConfig};
+
+    const CONTENT: &str = r#"; accounts
+2020-01-01 open Assets:Bank:Checking
+
+2020-01-05 * "Shop" "Paid 5 USD"
+    Assets:Bank:Checking   -1,200.5 USD
+  Expenses:Food 1200.50 USD ; dinner
+2020-01-06 balance Assets:Bank:Checking -1200.5 USD
+"#;
+
+    #[test]
+    fn align_to_widest_line() {
+        assert_eq!(
+            r#"; accounts
+2020-01-01 open Assets:Bank:Checking
+
+2020-01-05 * "Shop" "Paid 5 USD"
+  Assets:Bank:Checking                   -1,200.5 USD
+  Expenses:Food                           1200.50 USD ; dinner
+2020-01-06 balance Assets:Bank:Checking   -1200.5 USD
+"#,
+            format(CONTENT, &FormatConfig::default())
+        );
+    }
+
+    #[test]
+    fn normalize_posting_without_amount() {
+        let content = "2020-01-05 * \"Lunch\"\n  memo: \"paid\"\n\tAssets:Cash -10 USD\n      ! Expenses:Food\n    ; note\n";
+        assert_eq!(
+            "2020-01-05 * \"Lunch\"\n  memo: \"paid\"\n  Assets:Cash  -10 USD\n  ! Expenses:Food\n    ; note\n",
+            format(content, &FormatConfig::default())
+        );
+    }
+
+    #[test]
+    fn currency_column() {
+        let config = FormatConfig {
+            currency_column: Some(60),
+            ..FormatConfig::default()
+        };
+        let formatted = format(CONTENT, &config);
+        for line in formatted.lines().filter(|line| line.contains(" USD")) {
+            if !line.contains('"') {
+                assert_eq!(Some(60), line.find("USD"));
+            }
+        }
+        assert_eq!(formatted, format(&formatted, &config));
+    }
+}
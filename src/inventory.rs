@@ -0,0 +1,233 @@
+use crate::{models::Amount, prices::PriceMap};
+use bigdecimal::{BigDecimal, Zero};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// units of a commodity, optionally held at a per-unit cost
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Deserialize, Serialize)]
+pub struct Position {
+    pub units: Amount,
+    pub cost: Option<Amount>,
+}
+
+impl Position {
+    pub fn new(units: Amount, cost: Option<Amount>) -> Self {
+        Position { units, cost }
+    }
+
+    /// total cost of position, or its units if it is not held at cost
+    pub fn at_cost(&self) -> Amount {
+        match &self.cost {
+            Some((number, currency)) => (&self.units.0 * number, currency.clone()),
+            None => self.units.clone(),
+        }
+    }
+
+    /// market value of position in its cost currency, or its cost if no price is found
+    pub fn at_value(&self, prices: &PriceMap, date: &NaiveDate) -> Amount {
+        match &self.cost {
+            Some((_, currency)) => match prices.get(&self.units.1, currency, date) {
+                Some(price) => (&self.units.0 * price, currency.clone()),
+                None => self.at_cost(),
+            },
+            None => self.units.clone(),
+        }
+    }
+}
+
+/// a collection of positions, positions of same commodity and cost are merged
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Default, Deserialize, Serialize)]
+pub struct Inventory {
+    positions: Vec<Position>,
+}
+
+impl Inventory {
+    pub fn new() -> Self {
+        Inventory::default()
+    }
+
+    pub fn positions(&self) -> &[Position] {
+        &self.positions
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// add position into inventory, positions which sum to zero are removed
+    /// ```rust
+    /// use beancount::inventory::{Inventory, Position};
+    /// use bigdecimal::BigDecimal;
+    /// let mut inventory = Inventory::new();
+    /// inventory.add(Position::new((BigDecimal::from(10), "USD".to_owned()), None));
+    /// inventory.add(Position::new((BigDecimal::from(-4), "USD".to_owned()), None));
+    /// assert_eq!(BigDecimal::from(6), inventory.units_of("USD"));
+    /// inventory.add(Position::new((BigDecimal::from(-6), "USD".to_owned()), None));
+    /// assert!(inventory.is_empty());
+    /// ```
+    pub fn add(&mut self, position: Position) {
+        if position.units.0.is_zero() {
+            return;
+        }
+        let existing = self
+            .positions
+            .iter_mut()
+            .position(|p| p.units.1 == position.units.1 && p.cost == position.cost);
+        match existing {
+            Some(index) => {
+                let sum = &self.positions[index].units.0 + &position.units.0;
+                if sum.is_zero() {
+                    self.positions.remove(index);
+                } else {
+                    self.positions[index].units.0 = sum;
+                }
+            }
+            None => self.positions.push(position),
+        }
+    }
+
+    pub fn add_amount(&mut self, amount: Amount) {
+        self.add(Position::new(amount, None));
+    }
+
+    pub fn add_inventory(&mut self, other: &Inventory) {
+        for position in &other.positions {
+            self.add(position.clone());
+        }
+    }
+
+    /// sum of units of currency, regardless of cost
+    pub fn units_of(&self, currency: &str) -> BigDecimal {
+        self.positions
+            .iter()
+            .filter(|p| p.units.1 == currency)
+            .map(|p| &p.units.0)
+            .sum()
+    }
+
+    /// currencies of units, in order of appearance
+    pub fn currencies(&self) -> Vec<&str> {
+        let mut currencies: Vec<&str> = vec![];
+        for position in &self.positions {
+            if !currencies.contains(&position.units.1.as_str()) {
+                currencies.push(&position.units.1);
+            }
+        }
+        currencies
+    }
+
+    /// inventory without cost information
+    pub fn units(&self) -> Inventory {
+        self.map(|p| p.units.clone())
+    }
+
+    pub fn at_cost(&self) -> Inventory {
+        self.map(Position::at_cost)
+    }
+
+    pub fn at_value(&self, prices: &PriceMap, date: &NaiveDate) -> Inventory {
+        self.map(|p| p.at_value(prices, date))
+    }
+
+    /// market value converted into currency, positions without price are kept as they are
+    pub fn convert(&self, currency: &str, prices: &PriceMap, date: &NaiveDate) -> Inventory {
+        self.map(|p| {
+            let value = p.at_value(prices, date);
+            prices.convert(&value, currency, date).unwrap_or(value)
+        })
+    }
+
+    pub fn neg(&self) -> Inventory {
+        Inventory {
+            positions: self
+                .positions
+                .iter()
+                .map(|p| Position::new((-&p.units.0, p.units.1.clone()), p.cost.clone()))
+                .collect(),
+        }
+    }
+
+    fn map(&self, f: impl Fn(&Position) -> Amount) -> Inventory {
+        let mut inventory = Inventory::new();
+        for position in &self.positions {
+            inventory.add_amount(f(position));
+        }
+        inventory
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        inventory::{Inventory, Position},
+        prices::PriceMap,
+    };
+    use bigdecimal::BigDecimal;
+    use chrono::NaiveDate;
+    use std::str::FromStr;
+
+    fn amount(number: &str, currency: &str) -> (BigDecimal, String) {
+        (BigDecimal::from_str(number).unwrap(), currency.to_owned())
+    }
+
+    #[test]
+    fn merge_by_cost() {
+        let mut inventory = Inventory::new();
+        inventory.add(Position::new(
+            amount("10", "HOOL"),
+            Some(amount("500", "USD")),
+        ));
+        inventory.add(Position::new(
+            amount("5", "HOOL"),
+            Some(amount("510", "USD")),
+        ));
+        inventory.add(Position::new(
+            amount("-3", "HOOL"),
+            Some(amount("500", "USD")),
+        ));
+
+        assert_eq!(2, inventory.positions().len());
+        assert_eq!(BigDecimal::from(12), inventory.units_of("HOOL"));
+        assert_eq!(vec!["HOOL"], inventory.currencies());
+
+        let mut expected = Inventory::new();
+        expected.add_amount(amount("6050", "USD"));
+        assert_eq!(expected, inventory.at_cost());
+    }
+
+    #[test]
+    fn market_value() {
+        let mut prices = PriceMap::new();
+        let date = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap();
+        prices.insert(date, "HOOL", amount("520", "USD"));
+
+        let mut inventory = Inventory::new();
+        inventory.add(Position::new(
+            amount("10", "HOOL"),
+            Some(amount("500", "USD")),
+        ));
+        inventory.add_amount(amount("3", "CNY"));
+
+        let mut expected = Inventory::new();
+        expected.add_amount(amount("5200", "USD"));
+        expected.add_amount(amount("3", "CNY"));
+        assert_eq!(expected, inventory.at_value(&prices, &date));
+    }
+
+    #[test]
+    fn convert_into_currency() {
+        let mut prices = PriceMap::new();
+        let date = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap();
+        prices.insert(date, "USD", amount("7", "CNY"));
+
+        let mut inventory = Inventory::new();
+        inventory.add_amount(amount("10", "USD"));
+        inventory.add_amount(amount("3", "CNY"));
+        inventory.add_amount(amount("1", "EUR"));
+
+        let mut expected = Inventory::new();
+        expected.add_amount(amount("73", "CNY"));
+        expected.add_amount(amount("1", "EUR"));
+        assert_eq!(expected, inventory.convert("CNY", &prices, &date));
+    }
+}
@@ -1,6 +1,18 @@
 use lalrpop_util::lalrpop_mod;
+pub mod booking;
+pub mod document;
 pub mod error;
+pub mod format;
+pub mod inventory;
+pub mod loader;
 pub mod models;
+pub mod options;
+pub mod plugins;
+pub mod prices;
+pub mod query;
+pub mod realization;
+pub mod reports;
+pub mod summarize;
 pub mod to_file;
 
 pub(crate) mod utils;
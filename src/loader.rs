@@ -0,0 +1,355 @@
+use crate::{
+    document::{parse_chunk, Chunk, Chunker},
+    error::BeanCountError,
+    models::{AccountNames, Directive, Location},
+    options::Options,
+    parser::{EntryParser, OptionExpressionParser},
+    plugins::PluginRegistry,
+};
+use lalrpop_util::ParseError;
+use std::{collections::VecDeque, io::BufRead};
+
+/// directives of a file together with its options
+#[derive(Debug)]
+pub struct Ledger {
+    pub directives: Vec<Directive>,
+    pub options: Options,
+    /// errors which do not stop loading, like invalid options
+    pub errors: Vec<BeanCountError>,
+}
+
+/// load directives from content, honoring options which affect parsing like `name_assets`
+///
+/// Plugins declared in content run with the built-in plugins of `PluginRegistry::new`.
+pub fn load(content: &str) -> Result<Ledger, BeanCountError> {
+    load_with_plugins(content, &PluginRegistry::new())
+}
+
+/// load directives from content, running declared plugins found in registry
+/// ```rust
+/// use beancount::{loader::load_with_plugins, plugins::PluginRegistry};
+/// let mut registry = PluginRegistry::new();
+/// registry.register("drop_all", |_, _: &_, _: Option<&str>| (vec![], vec![]));
+/// let ledger = load_with_plugins("plugin \"drop_all\"\n1970-01-01 open Assets:Bank\n", &registry).unwrap();
+/// assert!(ledger.directives.is_empty() && ledger.errors.is_empty());
+/// ```
+pub fn load_with_plugins(
+    content: &str,
+    registry: &PluginRegistry,
+) -> Result<Ledger, BeanCountError> {
+    let names = account_names(content);
+    let mut directives = EntryParser::new()
+        .parse(&names, content)
+        .map_err(syntax_error)?;
+    locate(&mut directives, content, 1);
+    let (options, mut errors) = Options::from_directives(&directives);
+    let (directives, plugin_errors) = registry.run(directives, &options);
+    errors.extend(plugin_errors);
+    Ok(Ledger {
+        directives,
+        options,
+        errors,
+    })
+}
+
+pub(crate) fn syntax_error<T>(error: ParseError<usize, T, BeanCountError>) -> BeanCountError {
+    match error {
+        ParseError::InvalidToken { location } | ParseError::UnrecognizedEOF { location, .. } => {
+            BeanCountError::SyntaxError(location)
+        }
+        ParseError::UnrecognizedToken {
+            token: (location, _, _),
+            ..
+        }
+        | ParseError::ExtraToken {
+            token: (location, _, _),
+        } => BeanCountError::SyntaxError(location),
+        ParseError::User { error } => error,
+    }
+}
+
+/// replace byte offsets of parsed transactions in `text` by their lines, `text` starts at
+/// `first_line` and directives are in the order of the text
+pub(crate) fn locate(directives: &mut [Directive], text: &str, first_line: usize) {
+    let mut line = first_line;
+    let mut position = 0;
+    for directive in directives {
+        if let Directive::Transaction(transaction) = directive {
+            if let Some(offset) = transaction.location.0 {
+                line += text[position..offset].matches('\n').count();
+                position = offset;
+                transaction.location = Location(Some(line));
+            }
+        }
+    }
+}
+
+/// options must be known before parsing accounts, so pick them up from option lines first
+pub(crate) fn account_names(content: &str) -> AccountNames {
+    let default_names = AccountNames::default();
+    let mut names = AccountNames::default();
+    for line in content.lines().filter(|line| line.starts_with("option")) {
+        if let Ok(Directive::Option { key, value, .. }) =
+            OptionExpressionParser::new().parse(&default_names, line.trim_end())
+        {
+            // invalid names are reported by `Options::from_directives`
+            names.set_option(&key, &value).ok();
+        }
+    }
+    names
+}
+
+/// read directives one by one from a reader, only the lines of the current directive are kept
+///
+/// Root name options like `name_assets` apply to directives after them. A syntax error is
+/// yielded for the broken directive only and reading goes on with the next one.
+/// ```rust
+/// use beancount::loader::DirectiveReader;
+/// let content = "; my ledger\n1970-01-01 open Assets:Bank\n1970-01-01 close Assets:Bank\n";
+/// let directives: Vec<_> = DirectiveReader::new(content.as_bytes()).collect();
+/// assert_eq!(3, directives.len());
+/// ```
+pub struct DirectiveReader<R> {
+    reader: R,
+    names: AccountNames,
+    chunker: Chunker,
+    chunks: VecDeque<Chunk>,
+    offset: usize,
+    line: usize,
+    finished: bool,
+}
+
+impl<R: BufRead> DirectiveReader<R> {
+    pub fn new(reader: R) -> Self {
+        DirectiveReader {
+            reader,
+            names: AccountNames::default(),
+            chunker: Chunker::default(),
+            chunks: VecDeque::new(),
+            offset: 0,
+            line: 1,
+            finished: false,
+        }
+    }
+
+    /// read lines until a chunk is completed
+    fn next_chunk(&mut self) -> Result<Option<Chunk>, BeanCountError> {
+        let mut line = String::new();
+        while self.chunks.is_empty() && !self.finished {
+            line.clear();
+            let read = self
+                .reader
+                .read_line(&mut line)
+                .map_err(|e| BeanCountError::Io(e.to_string()))?;
+            if read == 0 {
+                self.finished = true;
+                self.chunks.extend(self.chunker.finish());
+            } else {
+                self.chunks.extend(self.chunker.push(&line));
+            }
+        }
+        Ok(self.chunks.pop_front())
+    }
+}
+
+impl<R: BufRead> Iterator for DirectiveReader<R> {
+    type Item = Result<Directive, BeanCountError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let chunk = match self.next_chunk() {
+                Ok(chunk) => chunk?,
+                Err(error) => {
+                    self.finished = true;
+                    self.chunks.clear();
+                    return Some(Err(error));
+                }
+            };
+            let (offset, line) = (self.offset, self.line);
+            self.offset += chunk.text().len();
+            self.line += chunk.text().matches('\n').count();
+            match chunk {
+                Chunk::Directive(text) => {
+                    let directive = parse_chunk(&text, &self.names, offset, line);
+                    if let Ok(Directive::Option { key, value, .. }) = &directive {
+                        self.names.set_option(key, value).ok();
+                    }
+                    return Some(directive);
+                }
+                Chunk::Comment(text) if text.trim_start().starts_with(';') => {
+                    let content = text.trim_start().trim_end_matches(['\r', '\n']);
+                    return Some(Ok(Directive::Comment {
+                        content: content.to_owned(),
+                    }));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        error::BeanCountError,
+        loader::{load, DirectiveReader},
+        models::{Account, AccountType, Directive, Location},
+        to_file::ToBeancountFile,
+    };
+    use chrono::NaiveDate;
+    use std::io::BufReader;
+
+    #[test]
+    fn transaction_lines() {
+        let content = "; header\n1970-01-01 * \"Lunch\"\n  Assets:Bank -1 USD\n  Expenses:Food\n\n1970-01-02 * \"Dinner\"\n  Assets:Bank -1 USD\n  Expenses:Food\n";
+        let lines = |directives: Vec<Directive>| -> Vec<Location> {
+            directives
+                .into_iter()
+                .filter_map(|directive| match directive {
+                    Directive::Transaction(transaction) => Some(transaction.location),
+                    _ => None,
+                })
+                .collect()
+        };
+        let expected = vec![Location(Some(2)), Location(Some(6))];
+        assert_eq!(expected, lines(load(content).unwrap().directives));
+        let read = DirectiveReader::new(content.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(expected, lines(read));
+    }
+
+    #[test]
+    fn default_account_names() {
+        let ledger = load("1970-01-01 open Assets:Bank\n").unwrap();
+        assert_eq!(
+            vec![Directive::Open {
+                date: NaiveDate::from_ymd_opt(1970, 1, 1).unwrap(),
+                account: Account::new(AccountType::Assets, vec!["Bank".to_owned()]),
+                commodities: None,
+                comment: None,
+            }],
+            ledger.directives
+        );
+    }
+
+    #[test]
+    fn custom_account_names() {
+        let content = [
+            r#"option "name_assets" "Aktiva""#,
+            r#"option "name_expenses" "支出""#,
+            "1970-01-01 open Aktiva:Bank",
+            "1970-01-01 open 支出:Food",
+            "",
+        ]
+        .join("\n");
+        let ledger = load(&content).unwrap();
+        assert!(ledger.errors.is_empty());
+        assert_eq!(
+            Directive::Open {
+                date: NaiveDate::from_ymd_opt(1970, 1, 1).unwrap(),
+                account: Account::new(AccountType::Assets, vec!["Bank".to_owned()]),
+                commodities: None,
+                comment: None,
+            },
+            ledger.directives[2]
+        );
+        assert_eq!(
+            Directive::Open {
+                date: NaiveDate::from_ymd_opt(1970, 1, 1).unwrap(),
+                account: Account::new(AccountType::Expenses, vec!["Food".to_owned()]),
+                commodities: None,
+                comment: None,
+            },
+            ledger.directives[3]
+        );
+    }
+
+    #[test]
+    fn custom_account_names_round_trip() {
+        let content = "option \"name_assets\" \"Aktiva\"\n1970-01-01 open Aktiva:Bank\n";
+        let ledger = load(content).unwrap();
+        let text: String = ledger
+            .directives
+            .iter()
+            .map(|directive| format!("{}\n", directive.to_text()))
+            .collect();
+        assert_eq!(content, text);
+        assert_eq!(ledger.directives, load(&text).unwrap().directives);
+    }
+
+    #[test]
+    fn single_letter_account_root() {
+        let content =
+            "option \"name_assets\" \"C\"\n1970-01-01 open C:Bank\n1970-01-02 * \"x\" #T\n  C:Bank -1 USD\n  U C:Bank 1 USD\n";
+        let ledger = load(content).unwrap();
+        let text: String = ledger
+            .directives
+            .iter()
+            .map(|directive| format!("{}\n", directive.to_text()))
+            .collect();
+        assert_eq!(content, text);
+    }
+
+    #[test]
+    fn invalid_account_name_is_not_applied() {
+        let content = "option \"name_income\" \"income\"\n1970-01-01 open income:Salary\n";
+        assert_eq!(BeanCountError::InvalidAccount, load(content).unwrap_err());
+        let ledger =
+            load("option \"name_income\" \"income\"\n1970-01-01 open Income:Salary\n").unwrap();
+        assert_eq!(
+            vec![BeanCountError::InvalidOption(
+                "name_income".to_owned(),
+                "income".to_owned()
+            )],
+            ledger.errors
+        );
+    }
+
+    #[test]
+    fn replaced_account_name_is_invalid() {
+        let content = "option \"name_assets\" \"Aktiva\"\n1970-01-01 open Assets:Bank\n";
+        assert_eq!(BeanCountError::InvalidAccount, load(content).unwrap_err());
+    }
+
+    #[test]
+    fn options_errors_do_not_stop_loading() {
+        let content = "option \"booking_method\" \"RANDOM\"\noption \"title\" \"Ledger\"\n";
+        let ledger = load(content).unwrap();
+        assert_eq!("Ledger", ledger.options.title);
+        assert_eq!(
+            vec![BeanCountError::InvalidOption(
+                "booking_method".to_owned(),
+                "RANDOM".to_owned()
+            )],
+            ledger.errors
+        );
+    }
+
+    #[test]
+    fn read_same_directives_as_load() {
+        let content = r#"option "name_assets" "Aktiva"
+; accounts
+1970-01-01 open Aktiva:Bank   USD ; checking
+
+1970-01-01 open Expenses:Food
+1970-01-05 * "Shop" "Lunch"
+  Aktiva:Bank  -20 USD
+  Expenses:Food
+1970-01-06 balance Aktiva:Bank -20 USD
+"#;
+        let reader = BufReader::with_capacity(8, content.as_bytes());
+        let directives: Result<Vec<Directive>, BeanCountError> =
+            DirectiveReader::new(reader).collect();
+        assert_eq!(load(content).unwrap().directives, directives.unwrap());
+    }
+
+    #[test]
+    fn syntax_error_does_not_stop_reading() {
+        let content = "1970-01-01 open Assets:Bank\n1970-01-01 opn Assets:Bank\n\n1970-01-01 close Assets:Bank\n";
+        let results: Vec<_> = DirectiveReader::new(content.as_bytes()).collect();
+        assert_eq!(3, results.len());
+        assert_eq!(Err(BeanCountError::SyntaxError(39)), results[1]);
+        assert!(matches!(results[2], Ok(Directive::Close { .. })));
+    }
+}
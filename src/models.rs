@@ -4,80 +4,176 @@ use chrono::NaiveDate;
 use indexmap::IndexMap;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize, Serializer};
-use std::str::FromStr;
+use std::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+    str::FromStr,
+};
 use strum_macros::EnumString;
 
 pub type Amount = (BigDecimal, String);
 
-#[derive(Debug, PartialEq, Deserialize, Serialize)]
+/// value of a `custom` directive, typed so that it is written back the way it was given
+#[derive(Debug, PartialEq, Deserialize, Serialize, Clone)]
+pub enum CustomValue {
+    String(String),
+    Amount(Amount),
+    Account(Account),
+    /// unquoted word, like a commodity or a boolean
+    Word(String),
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize, Clone)]
 #[serde(tag = "type")]
+/// `comment` of variants keeps the trailing `; comment` of the directive header
 pub enum Directive {
     Open {
         date: NaiveDate,
         account: Account,
         commodities: Option<Vec<String>>,
+        comment: Option<String>,
     },
     Close {
         date: NaiveDate,
         account: Account,
+        comment: Option<String>,
     },
     Commodity {
         date: NaiveDate,
         name: String,
         metas: IndexMap<String, String>,
+        comment: Option<String>,
     },
     Transaction(Transaction),
     Balance {
         date: NaiveDate,
         account: Account,
         amount: Amount,
+        comment: Option<String>,
     },
     Pad {
         date: NaiveDate,
         from: Account,
         to: Account,
+        comment: Option<String>,
     },
     Note {
         date: NaiveDate,
         account: Account,
         description: String,
+        comment: Option<String>,
     },
     Document {
         date: NaiveDate,
         account: Account,
         path: String,
+        comment: Option<String>,
     },
     Price {
         date: NaiveDate,
         commodity: String,
         amount: Amount,
+        comment: Option<String>,
     },
     Event {
         date: NaiveDate,
         name: String,
         value: String,
+        comment: Option<String>,
     },
     Custom {
         date: NaiveDate,
         type_name: String,
-        values: Vec<String>,
+        values: Vec<CustomValue>,
+        comment: Option<String>,
     },
     Option {
         key: String,
         value: String,
+        comment: Option<String>,
     },
     Plugin {
         module: String,
         value: Option<String>,
+        comment: Option<String>,
     },
     Include {
         file: String,
+        comment: Option<String>,
     },
     Comment {
         content: String,
     },
 }
 
+impl Directive {
+    /// date of directive, undated directives like `option` return `None`
+    pub fn date(&self) -> Option<&NaiveDate> {
+        match self {
+            Directive::Open { date, .. }
+            | Directive::Close { date, .. }
+            | Directive::Commodity { date, .. }
+            | Directive::Balance { date, .. }
+            | Directive::Pad { date, .. }
+            | Directive::Note { date, .. }
+            | Directive::Document { date, .. }
+            | Directive::Price { date, .. }
+            | Directive::Event { date, .. }
+            | Directive::Custom { date, .. } => Some(date),
+            Directive::Transaction(transaction) => Some(&transaction.date),
+            Directive::Option { .. }
+            | Directive::Plugin { .. }
+            | Directive::Include { .. }
+            | Directive::Comment { .. } => None,
+        }
+    }
+
+    /// accounts referenced by directive, in order of postings for transaction
+    /// ```rust
+    /// use beancount::{loader::load, models::Account};
+    /// use std::str::FromStr;
+    /// let ledger = load("1970-01-01 pad Assets:Bank Equity:Opening\n").unwrap();
+    /// assert_eq!(
+    ///     vec![&Account::from_str("Assets:Bank").unwrap(), &Account::from_str("Equity:Opening").unwrap()],
+    ///     ledger.directives[0].accounts()
+    /// );
+    /// ```
+    pub fn accounts(&self) -> Vec<&Account> {
+        match self {
+            Directive::Open { account, .. }
+            | Directive::Close { account, .. }
+            | Directive::Balance { account, .. }
+            | Directive::Note { account, .. }
+            | Directive::Document { account, .. } => vec![account],
+            Directive::Pad { from, to, .. } => vec![from, to],
+            Directive::Transaction(transaction) => {
+                transaction.lines.iter().map(|line| &line.account).collect()
+            }
+            _ => vec![],
+        }
+    }
+
+    /// order of directives in the same day, balance is checked at the beginning of the day
+    fn type_order(&self) -> i8 {
+        match self {
+            Directive::Open { .. } => -2,
+            Directive::Balance { .. } => -1,
+            Directive::Document { .. } => 1,
+            Directive::Close { .. } => 2,
+            _ => 0,
+        }
+    }
+}
+
+/// stable sort directives by date, undated directives come first
+pub fn sort_directives(directives: &mut [Directive]) {
+    directives.sort_by(|a, b| {
+        a.date()
+            .cmp(&b.date())
+            .then_with(|| a.type_order().cmp(&b.type_order()))
+    });
+}
+
 #[derive(
     Debug,
     EnumString,
@@ -88,6 +184,7 @@ pub enum Directive {
     Serialize,
     Clone,
     Eq,
+    Ord,
     Hash,
 )]
 pub enum AccountType {
@@ -98,32 +195,178 @@ pub enum AccountType {
     Expenses,
 }
 
-#[derive(Debug, PartialEq, PartialOrd, Deserialize, Clone, Eq, Hash)]
+/// root names of the five account types, configured by `name_*` options
+#[derive(Debug, PartialEq, Deserialize, Serialize, Clone, Eq)]
+pub struct AccountNames {
+    pub assets: String,
+    pub liabilities: String,
+    pub equity: String,
+    pub income: String,
+    pub expenses: String,
+}
+
+impl Default for AccountNames {
+    fn default() -> Self {
+        AccountNames {
+            assets: AccountType::Assets.to_string(),
+            liabilities: AccountType::Liabilities.to_string(),
+            equity: AccountType::Equity.to_string(),
+            income: AccountType::Income.to_string(),
+            expenses: AccountType::Expenses.to_string(),
+        }
+    }
+}
+
+impl AccountNames {
+    /// find the account type of given root name
+    /// ```rust
+    /// use beancount::models::{AccountNames, AccountType};
+    /// let mut names = AccountNames::default();
+    /// assert_eq!(Some(AccountType::Assets), names.account_type("Assets"));
+    /// names.set_option("name_assets", "Aktiva").unwrap();
+    /// assert_eq!(Some(AccountType::Assets), names.account_type("Aktiva"));
+    /// assert_eq!(None, names.account_type("Assets"));
+    /// ```
+    pub fn account_type(&self, root: &str) -> Option<AccountType> {
+        [
+            AccountType::Assets,
+            AccountType::Liabilities,
+            AccountType::Equity,
+            AccountType::Income,
+            AccountType::Expenses,
+        ]
+        .iter()
+        .find(|account_type| self.root(account_type) == root)
+        .cloned()
+    }
+
+    /// the root name of given account type
+    pub fn root(&self, account_type: &AccountType) -> &str {
+        match account_type {
+            AccountType::Assets => &self.assets,
+            AccountType::Liabilities => &self.liabilities,
+            AccountType::Equity => &self.equity,
+            AccountType::Income => &self.income,
+            AccountType::Expenses => &self.expenses,
+        }
+    }
+
+    /// account of given type under the configured root name
+    /// ```rust
+    /// use beancount::models::{AccountNames, AccountType};
+    /// let mut names = AccountNames::default();
+    /// names.set_option("name_assets", "Aktiva").unwrap();
+    /// let account = names.account(AccountType::Assets, vec!["Bank".to_owned()]);
+    /// assert_eq!("Aktiva:Bank", account.to_string());
+    /// ```
+    pub fn account(&self, account_type: AccountType, value: Vec<String>) -> Account {
+        Account {
+            root: self.root(&account_type).to_owned(),
+            account_type,
+            value,
+        }
+    }
+
+    /// parse account name using the configured root names
+    /// ```rust
+    /// use beancount::models::{AccountNames, AccountType};
+    /// let mut names = AccountNames::default();
+    /// names.set_option("name_expenses", "Ausgaben").unwrap();
+    /// let account = names.parse_account("Ausgaben:Food").unwrap();
+    /// assert!(account.is_expense());
+    /// assert_eq!("Ausgaben:Food", account.to_string());
+    /// assert!(names.parse_account("Expenses:Food").is_err());
+    /// ```
+    pub fn parse_account(&self, s: &str) -> Result<Account, BeanCountError> {
+        AccountExpressionParser::new()
+            .parse(self, s)
+            .map_err(|_| BeanCountError::InvalidAccount)
+    }
+
+    /// apply `name_*` option, return false if key is not a root name option
+    ///
+    /// A root name must start with a capital letter and contain only letters, digits and `-`.
+    /// ```rust
+    /// use beancount::models::AccountNames;
+    /// let mut names = AccountNames::default();
+    /// assert_eq!(Ok(true), names.set_option("name_income", "Ertrag"));
+    /// assert_eq!(Ok(false), names.set_option("title", "Ledger"));
+    /// assert!(names.set_option("name_income", "income").is_err());
+    /// assert_eq!("Ertrag", names.income);
+    /// ```
+    pub fn set_option(&mut self, key: &str, value: &str) -> Result<bool, BeanCountError> {
+        let root = match key {
+            "name_assets" => &mut self.assets,
+            "name_liabilities" => &mut self.liabilities,
+            "name_equity" => &mut self.equity,
+            "name_income" => &mut self.income,
+            "name_expenses" => &mut self.expenses,
+            _ => return Ok(false),
+        };
+        let valid = value.chars().next().is_some_and(|c| !c.is_lowercase())
+            && value.chars().all(|c| c.is_alphanumeric() || c == '-');
+        if !valid {
+            return Err(BeanCountError::InvalidOption(
+                key.to_owned(),
+                value.to_owned(),
+            ));
+        }
+        *root = value.to_owned();
+        Ok(true)
+    }
+}
+
+/// account identified by its type and components
+///
+/// The root name is kept for rendering only, so accounts of a ledger with `name_*` options equal
+/// those built by `Account::new`.
+#[derive(Debug, Deserialize, Clone)]
 pub struct Account {
     account_type: AccountType,
+    root: String,
     value: Vec<String>,
 }
 
+impl PartialEq for Account {
+    fn eq(&self, other: &Self) -> bool {
+        self.account_type == other.account_type && self.value == other.value
+    }
+}
+
+impl Eq for Account {}
+
+impl PartialOrd for Account {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Account {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (&self.account_type, &self.value).cmp(&(&other.account_type, &other.value))
+    }
+}
+
+impl Hash for Account {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.account_type.hash(state);
+        self.value.hash(state);
+    }
+}
+
 impl Serialize for Account {
     fn serialize<S>(&self, serializer: S) -> Result<<S as Serializer>::Ok, <S as Serializer>::Error>
     where
         S: Serializer,
     {
-        serializer.serialize_str(&format!(
-            "{}{}{}",
-            self.account_type.to_string(),
-            if self.value.is_empty() { "" } else { ":" },
-            self.value.join(":")
-        ))
+        serializer.serialize_str(&self.to_string())
     }
 }
 
 impl Account {
+    /// account under the default root name of its type, see `AccountNames::account`
     pub fn new(account_type: AccountType, value: Vec<String>) -> Self {
-        Account {
-            account_type,
-            value,
-        }
+        AccountNames::default().account(account_type, value)
     }
 
     /// assert whether account is Asset
@@ -184,9 +427,61 @@ impl Account {
     pub fn is_account_type(&self, atype: AccountType) -> bool {
         self.account_type == atype
     }
+
+    pub fn account_type(&self) -> &AccountType {
+        &self.account_type
+    }
+
+    /// root name the account was written with, like `Assets` or `Aktiva`
+    pub fn root(&self) -> &str {
+        &self.root
+    }
+
+    /// account with the same type and root name but other components
+    pub fn with_components(&self, value: Vec<String>) -> Account {
+        Account {
+            account_type: self.account_type.clone(),
+            root: self.root.clone(),
+            value,
+        }
+    }
+
+    /// components of account name after the root
+    pub fn components(&self) -> &[String] {
+        &self.value
+    }
+
+    /// parent account, root account like `Assets` has no parent
+    /// ```rust
+    /// use beancount::models::Account;
+    /// use std::str::FromStr;
+    /// let account = Account::from_str("Assets:A:B").unwrap();
+    /// assert_eq!(Some(Account::from_str("Assets:A").unwrap()), account.parent());
+    /// assert!(account.parent().unwrap().parent().unwrap().parent().is_none());
+    /// ```
+    pub fn parent(&self) -> Option<Account> {
+        if self.value.is_empty() {
+            return None;
+        }
+        Some(self.with_components(self.value[..self.value.len() - 1].to_vec()))
+    }
+
+    /// check whether account is self or under self
+    /// ```rust
+    /// use beancount::models::Account;
+    /// use std::str::FromStr;
+    /// let parent = Account::from_str("Assets:A").unwrap();
+    /// assert!(parent.contains(&Account::from_str("Assets:A:B").unwrap()));
+    /// assert!(parent.contains(&parent));
+    /// assert!(!parent.contains(&Account::from_str("Assets:AB").unwrap()));
+    /// ```
+    pub fn contains(&self, other: &Account) -> bool {
+        self.account_type == other.account_type && other.value.starts_with(&self.value)
+    }
 }
 
-///
+/// parse account with the default root names, use `AccountNames::parse_account` for a ledger
+/// with `name_*` options
 /// ```rust
 /// use beancount::models::Account;
 /// use std::str::FromStr;
@@ -198,14 +493,27 @@ impl FromStr for Account {
     type Err = BeanCountError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        AccountExpressionParser::new()
-            .parse(s)
-            .map_err(|_| BeanCountError::InvalidAccount)
+        AccountNames::default().parse_account(s)
+    }
+}
+
+/// line of a directive in the parsed text counted from one, `None` for directives built in code
+#[derive(
+    Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize, Clone, Copy,
+)]
+pub struct Location(pub Option<usize>);
+
+impl std::fmt::Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Some(line) => write!(f, "at line {}", line),
+            None => write!(f, "at unknown location"),
+        }
     }
 }
 
 // todo tags links
-#[derive(Debug, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Transaction {
     pub date: NaiveDate,
     pub flag: Flag,
@@ -214,9 +522,51 @@ pub struct Transaction {
     pub tags: Vec<String>,
     pub links: Vec<String>,
     pub lines: Vec<TransactionLine>,
+    /// trailing `; comment` of the header line
+    pub comment: Option<String>,
+    /// ignored when comparing, so a transaction equals the same one parsed elsewhere
+    pub location: Location,
+}
+
+type TransactionKey<'a> = (
+    &'a NaiveDate,
+    &'a Flag,
+    &'a Option<String>,
+    &'a Option<String>,
+    &'a Vec<String>,
+    &'a Vec<String>,
+    &'a Vec<TransactionLine>,
+    &'a Option<String>,
+);
+
+impl Transaction {
+    fn key(&self) -> TransactionKey<'_> {
+        (
+            &self.date,
+            &self.flag,
+            &self.payee,
+            &self.narration,
+            &self.tags,
+            &self.links,
+            &self.lines,
+            &self.comment,
+        )
+    }
 }
 
-#[derive(Debug, PartialEq, PartialOrd, Deserialize, Serialize)]
+impl PartialEq for Transaction {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl PartialOrd for Transaction {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.key().partial_cmp(&other.key())
+    }
+}
+
+#[derive(Debug, PartialEq, PartialOrd, Deserialize, Serialize, Clone)]
 pub struct TransactionLine {
     pub flag: Flag,
     pub account: Account,
@@ -224,16 +574,44 @@ pub struct TransactionLine {
     pub cost: Option<(Amount, Option<String>)>,
     pub single_price: Option<Amount>,
     pub total_price: Option<Amount>,
+    /// trailing `; comment` of the posting line
+    pub comment: Option<String>,
 }
 
 #[derive(
-    EnumString, Debug, PartialEq, PartialOrd, strum_macros::ToString, Deserialize, Serialize,
+    EnumString,
+    Debug,
+    PartialEq,
+    PartialOrd,
+    strum_macros::ToString,
+    Deserialize,
+    Serialize,
+    Clone,
+    Copy,
 )]
 pub enum Flag {
     #[strum(serialize = "*", to_string = "*")]
     Complete,
     #[strum(serialize = "!", to_string = "!")]
     Incomplete,
+    /// transaction inserted by `pad` directive
+    #[strum(serialize = "P", to_string = "P")]
+    Padding,
+    /// opening balance inserted when summarizing previous entries
+    #[strum(serialize = "S", to_string = "S")]
+    Summarize,
+    /// balance transferred into equity when closing a period
+    #[strum(serialize = "T", to_string = "T")]
+    Transfer,
+    /// residual of currency conversions
+    #[strum(serialize = "C", to_string = "C")]
+    Conversion,
+    /// unrealized gain of positions held at cost
+    #[strum(serialize = "U", to_string = "U")]
+    Unrealized,
+    /// transaction expected in the future, may recur
+    #[strum(serialize = "#", to_string = "#")]
+    Forecast,
 }
 
 pub(crate) fn amount_parse(input: &str) -> Amount {
@@ -245,7 +623,7 @@ pub(crate) fn amount_parse(input: &str) -> Amount {
 impl ToString for Account {
     fn to_string(&self) -> String {
         let map = self.value.iter().map(|p| format!(":{}", p)).join("");
-        format!("{}{}", self.account_type.to_string(), map)
+        format!("{}{}", self.root, map)
     }
 }
 
@@ -267,16 +645,19 @@ impl Transaction {
             tags,
             links,
             lines,
+            comment: None,
+            location: Location::default(),
         }
     }
 
+    /// `offset` is the byte offset of the header until `loader::locate` turns it into a line
     pub(crate) fn from_parser(
         date: NaiveDate,
         flag: Flag,
-        pn: Option<(String, Option<String>)>,
-        tags: Vec<String>,
-        links: Vec<String>,
+        (pn, tags, links): TransactionHead,
         lines: Vec<TransactionLine>,
+        comment: Option<String>,
+        offset: usize,
     ) -> Transaction {
         let (payee, narration) = match pn {
             None => (None, None),
@@ -292,10 +673,14 @@ impl Transaction {
             tags,
             links,
             lines,
+            comment,
+            location: Location(Some(offset)),
         }
     }
 }
 
+pub(crate) type TransactionHead = (Option<(String, Option<String>)>, Vec<String>, Vec<String>);
+
 pub(crate) type AmountInfo = (
     Amount,
     Option<(Amount, Option<String>)>,
@@ -308,6 +693,7 @@ impl TransactionLine {
         flag: Option<Flag>,
         account: Account,
         amount_info: Option<AmountInfo>,
+        comment: Option<String>,
     ) -> Self {
         let flag = flag.unwrap_or(Flag::Complete);
         let (amount, cost, single_price, total_price) = match amount_info {
@@ -322,6 +708,7 @@ impl TransactionLine {
             cost,
             single_price,
             total_price,
+            comment,
         }
     }
 }
@@ -330,7 +717,7 @@ impl TransactionLine {
 mod test {
     mod open {
         use crate::{
-            models::{Account, AccountType, Directive},
+            models::{Account, AccountNames, AccountType, Directive},
             parser::DirectiveExpressionParser,
         };
         use chrono::NaiveDate;
@@ -351,9 +738,13 @@ mod test {
                     ],
                 ),
                 commodities: None,
+                comment: None,
             };
             let x = DirectiveExpressionParser::new()
-                .parse("1970-01-01 open Assets:123:234:English:中文:日本語:한국어")
+                .parse(
+                    &AccountNames::default(),
+                    "1970-01-01 open Assets:123:234:English:中文:日本語:한국어",
+                )
                 .unwrap();
             assert_eq!(directive, x);
         }
@@ -374,9 +765,13 @@ mod test {
                     ],
                 ),
                 commodities: Some(vec!["CNY".to_owned()]),
+                comment: None,
             };
             let x = DirectiveExpressionParser::new()
-                .parse("1970-01-01 open Assets:123:234:English:中文:日本語:한국어 CNY")
+                .parse(
+                    &AccountNames::default(),
+                    "1970-01-01 open Assets:123:234:English:中文:日本語:한국어 CNY",
+                )
                 .unwrap();
             assert_eq!(directive, x);
         }
@@ -397,9 +792,13 @@ mod test {
                     ],
                 ),
                 commodities: Some(vec!["CNY".to_owned(), "USD".to_owned(), "CAD".to_owned()]),
+                comment: None,
             };
             let x = DirectiveExpressionParser::new()
-                .parse("1970-01-01 open Assets:123:234:English:中文:日本語:한국어 CNY, USD,CAD")
+                .parse(
+                    &AccountNames::default(),
+                    "1970-01-01 open Assets:123:234:English:中文:日本語:한국어 CNY, USD,CAD",
+                )
                 .unwrap();
             assert_eq!(directive, x);
         }
@@ -407,7 +806,7 @@ mod test {
 
     mod close {
         use crate::{
-            models::{Account, AccountType, Directive},
+            models::{Account, AccountNames, AccountType, Directive},
             parser::DirectiveExpressionParser,
         };
         use chrono::NaiveDate;
@@ -420,9 +819,13 @@ mod test {
                     AccountType::Assets,
                     vec!["123".to_owned(), "456".to_owned()],
                 ),
+                comment: None,
             };
             let x = DirectiveExpressionParser::new()
-                .parse(r#"1970-01-01 close Assets:123:456  "#)
+                .parse(
+                    &AccountNames::default(),
+                    r#"1970-01-01 close Assets:123:456  "#,
+                )
                 .unwrap();
             assert_eq!(directive, x);
         }
@@ -430,7 +833,7 @@ mod test {
 
     mod note {
         use crate::{
-            models::{Account, AccountType, Directive},
+            models::{Account, AccountNames, AccountType, Directive},
             parser::DirectiveExpressionParser,
         };
         use chrono::NaiveDate;
@@ -441,29 +844,37 @@ mod test {
                 date: NaiveDate::from_ymd(1970, 1, 1),
                 account: Account::new(AccountType::Assets, vec!["123".to_owned()]),
                 description: "你 好 啊\\".to_owned(),
+                comment: None,
             };
             let x = DirectiveExpressionParser::new()
-                .parse(r#"1970-01-01 note Assets:123 "你 好 啊\\""#)
+                .parse(
+                    &AccountNames::default(),
+                    r#"1970-01-01 note Assets:123 "你 好 啊\\""#,
+                )
                 .unwrap();
             assert_eq!(directive, x);
         }
     }
 
     mod commodity {
-        use crate::{models::Directive, parser::DirectiveExpressionParser};
+        use crate::{
+            models::{AccountNames, Directive},
+            parser::DirectiveExpressionParser,
+        };
         use chrono::NaiveDate;
         use indexmap::IndexMap;
 
         #[test]
         fn test_commodity_without_attribute() {
             let x = DirectiveExpressionParser::new()
-                .parse(r#"1970-01-01 commodity CNY  "#)
+                .parse(&AccountNames::default(), r#"1970-01-01 commodity CNY  "#)
                 .unwrap();
 
             let directive = Directive::Commodity {
                 date: NaiveDate::from_ymd(1970, 1, 1),
                 name: "CNY".to_owned(),
                 metas: IndexMap::new(),
+                comment: None,
             };
             assert_eq!(directive, x);
         }
@@ -472,6 +883,7 @@ mod test {
         fn test_commodity_with_single_attribute() {
             let x = DirectiveExpressionParser::new()
                 .parse(
+                    &AccountNames::default(),
                     r#"1970-01-01 commodity CNY
                   a: "b""#,
                 )
@@ -483,6 +895,7 @@ mod test {
                 date: NaiveDate::from_ymd(1970, 1, 1),
                 name: "CNY".to_owned(),
                 metas,
+                comment: None,
             };
             assert_eq!(directive, x);
         }
@@ -491,6 +904,7 @@ mod test {
         fn test_commodity_with_attributes() {
             let x = DirectiveExpressionParser::new()
                 .parse(
+                    &AccountNames::default(),
                     r#"1970-01-01 commodity CNY
                   a: "b"
                   中文-test  :  "한국어 我也不知道我在说啥""#,
@@ -507,6 +921,7 @@ mod test {
                 date: NaiveDate::from_ymd(1970, 1, 1),
                 name: "CNY".to_owned(),
                 metas,
+                comment: None,
             };
             assert_eq!(directive, x);
         }
@@ -514,7 +929,10 @@ mod test {
 
     mod transaction {
         use crate::{
-            models::{Account, AccountType, Directive, Flag, Transaction, TransactionLine},
+            models::{
+                Account, AccountNames, AccountType, Directive, Flag, Location, Transaction,
+                TransactionLine,
+            },
             parser::DirectiveExpressionParser,
         };
         use bigdecimal::{BigDecimal, FromPrimitive};
@@ -524,6 +942,7 @@ mod test {
         fn simple_test() {
             let x = DirectiveExpressionParser::new()
                 .parse(
+                    &AccountNames::default(),
                     r#"1970-01-01 * "Payee" "Narration"
                   Assets:123  -1 CNY
                   Expenses:TestCategory:One 1 CNY"#,
@@ -537,6 +956,7 @@ mod test {
                 cost: None,
                 single_price: None,
                 total_price: None,
+                comment: None,
             };
             let b = TransactionLine {
                 flag: Flag::Complete,
@@ -548,6 +968,7 @@ mod test {
                 cost: None,
                 single_price: None,
                 total_price: None,
+                comment: None,
             };
 
             let transaction = Transaction {
@@ -558,6 +979,8 @@ mod test {
                 tags: vec![],
                 links: vec![],
                 lines: vec![a, b],
+                comment: None,
+                location: Location::default(),
             };
             let x1 = Directive::Transaction(transaction);
 
@@ -568,6 +991,7 @@ mod test {
         fn without_payee_with_narration() {
             let x = DirectiveExpressionParser::new()
                 .parse(
+                    &AccountNames::default(),
                     r#"1970-01-01 * "Narration"
                   Assets:123  -1 CNY
                   Expenses:TestCategory:One 1 CNY"#,
@@ -581,6 +1005,7 @@ mod test {
                 cost: None,
                 single_price: None,
                 total_price: None,
+                comment: None,
             };
             let b = TransactionLine {
                 flag: Flag::Complete,
@@ -592,6 +1017,7 @@ mod test {
                 cost: None,
                 single_price: None,
                 total_price: None,
+                comment: None,
             };
 
             let transaction = Transaction {
@@ -602,6 +1028,8 @@ mod test {
                 tags: vec![],
                 links: vec![],
                 lines: vec![a, b],
+                comment: None,
+                location: Location::default(),
             };
             let x1 = Directive::Transaction(transaction);
 
@@ -612,6 +1040,7 @@ mod test {
         fn cost_and_cost_comment() {
             let x = DirectiveExpressionParser::new()
                 .parse(
+                    &AccountNames::default(),
                     r#"1970-01-01 * "Narration"
                   Assets:123  -1 CNY {0.1 USD , "TEST"}
                   Expenses:TestCategory:One 1 CNY {0.1 USD}"#,
@@ -628,6 +1057,7 @@ mod test {
                 )),
                 single_price: None,
                 total_price: None,
+                comment: None,
             };
             let b = TransactionLine {
                 flag: Flag::Complete,
@@ -642,6 +1072,7 @@ mod test {
                 )),
                 single_price: None,
                 total_price: None,
+                comment: None,
             };
 
             let transaction = Transaction {
@@ -652,6 +1083,8 @@ mod test {
                 tags: vec![],
                 links: vec![],
                 lines: vec![a, b],
+                comment: None,
+                location: Location::default(),
             };
             let x1 = Directive::Transaction(transaction);
 
@@ -662,6 +1095,7 @@ mod test {
         fn multiple_transaction_lines() {
             let x = DirectiveExpressionParser::new()
                 .parse(
+                    &AccountNames::default(),
                     r#"1970-01-01 * "Payee" "Narration"
                   Assets:123  -1 CNY
                   Expenses:TestCategory:One 0.5 CNY
@@ -676,6 +1110,7 @@ mod test {
                 cost: None,
                 single_price: None,
                 total_price: None,
+                comment: None,
             };
             let b = TransactionLine {
                 flag: Flag::Complete,
@@ -687,6 +1122,7 @@ mod test {
                 cost: None,
                 single_price: None,
                 total_price: None,
+                comment: None,
             };
             let c = TransactionLine {
                 flag: Flag::Complete,
@@ -698,6 +1134,7 @@ mod test {
                 cost: None,
                 single_price: None,
                 total_price: None,
+                comment: None,
             };
 
             let transaction = Transaction {
@@ -708,6 +1145,8 @@ mod test {
                 tags: vec![],
                 links: vec![],
                 lines: vec![a, b, c],
+                comment: None,
+                location: Location::default(),
             };
             let x1 = Directive::Transaction(transaction);
 
@@ -718,6 +1157,7 @@ mod test {
         fn optional_amount_in_line() {
             let x = DirectiveExpressionParser::new()
                 .parse(
+                    &AccountNames::default(),
                     r#"1970-01-01 * "Payee" "Narration"
                   Assets:123  -1 CNY
                   Expenses:TestCategory:One"#,
@@ -731,6 +1171,7 @@ mod test {
                 cost: None,
                 single_price: None,
                 total_price: None,
+                comment: None,
             };
             let b = TransactionLine {
                 flag: Flag::Complete,
@@ -742,6 +1183,7 @@ mod test {
                 cost: None,
                 single_price: None,
                 total_price: None,
+                comment: None,
             };
 
             let transaction = Transaction {
@@ -752,6 +1194,8 @@ mod test {
                 tags: vec![],
                 links: vec![],
                 lines: vec![a, b],
+                comment: None,
+                location: Location::default(),
             };
             let x1 = Directive::Transaction(transaction);
 
@@ -762,6 +1206,7 @@ mod test {
         fn optional_single_price() {
             let x = DirectiveExpressionParser::new()
                 .parse(
+                    &AccountNames::default(),
                     r#"1970-01-01 * "Payee" "Narration"
                   Assets:123  -1 CNY
                   Expenses:TestCategory:One 1 CCC @ 1 CNY"#,
@@ -775,6 +1220,7 @@ mod test {
                 cost: None,
                 single_price: None,
                 total_price: None,
+                comment: None,
             };
             let b = TransactionLine {
                 flag: Flag::Complete,
@@ -786,6 +1232,7 @@ mod test {
                 cost: None,
                 single_price: Some((BigDecimal::from(1i16), "CNY".to_string())),
                 total_price: None,
+                comment: None,
             };
 
             let transaction = Transaction {
@@ -796,6 +1243,8 @@ mod test {
                 tags: vec![],
                 links: vec![],
                 lines: vec![a, b],
+                comment: None,
+                location: Location::default(),
             };
             let x1 = Directive::Transaction(transaction);
 
@@ -806,6 +1255,7 @@ mod test {
         fn optional_total_price() {
             let x = DirectiveExpressionParser::new()
                 .parse(
+                    &AccountNames::default(),
                     r#"1970-01-01 * "Payee" "Narration"
                   Assets:123  -1 CNY
                   Expenses:TestCategory:One 1 CCC @@ 1 CNY"#,
@@ -819,6 +1269,7 @@ mod test {
                 cost: None,
                 single_price: None,
                 total_price: None,
+                comment: None,
             };
             let b = TransactionLine {
                 flag: Flag::Complete,
@@ -830,6 +1281,7 @@ mod test {
                 cost: None,
                 single_price: None,
                 total_price: Some((BigDecimal::from(1i16), "CNY".to_string())),
+                comment: None,
             };
 
             let transaction = Transaction {
@@ -840,6 +1292,8 @@ mod test {
                 tags: vec![],
                 links: vec![],
                 lines: vec![a, b],
+                comment: None,
+                location: Location::default(),
             };
             let x1 = Directive::Transaction(transaction);
 
@@ -850,6 +1304,7 @@ mod test {
         fn with_optional_tags_without_payee() {
             let x = DirectiveExpressionParser::new()
                 .parse(
+                    &AccountNames::default(),
                     r#"1970-01-01 *  "Narration" #mytag #tag2
                   Assets:123  -1 CNY
                   Expenses:TestCategory:One 1 CCC @@ 1 CNY"#,
@@ -863,6 +1318,7 @@ mod test {
                 cost: None,
                 single_price: None,
                 total_price: None,
+                comment: None,
             };
             let b = TransactionLine {
                 flag: Flag::Complete,
@@ -874,6 +1330,7 @@ mod test {
                 cost: None,
                 single_price: None,
                 total_price: Some((BigDecimal::from(1i16), "CNY".to_string())),
+                comment: None,
             };
 
             let transaction = Transaction {
@@ -884,6 +1341,8 @@ mod test {
                 tags: vec!["mytag".to_owned(), "tag2".to_owned()],
                 links: vec![],
                 lines: vec![a, b],
+                comment: None,
+                location: Location::default(),
             };
             let x1 = Directive::Transaction(transaction);
 
@@ -894,6 +1353,7 @@ mod test {
         fn optional_tags() {
             let x = DirectiveExpressionParser::new()
                 .parse(
+                    &AccountNames::default(),
                     r#"1970-01-01 * "Payee" "Narration" #mytag #tag2
                   Assets:123  -1 CNY
                   Expenses:TestCategory:One 1 CCC @@ 1 CNY"#,
@@ -907,6 +1367,7 @@ mod test {
                 cost: None,
                 single_price: None,
                 total_price: None,
+                comment: None,
             };
             let b = TransactionLine {
                 flag: Flag::Complete,
@@ -918,6 +1379,7 @@ mod test {
                 cost: None,
                 single_price: None,
                 total_price: Some((BigDecimal::from(1i16), "CNY".to_string())),
+                comment: None,
             };
 
             let transaction = Transaction {
@@ -928,6 +1390,8 @@ mod test {
                 tags: vec!["mytag".to_owned(), "tag2".to_owned()],
                 links: vec![],
                 lines: vec![a, b],
+                comment: None,
+                location: Location::default(),
             };
             let x1 = Directive::Transaction(transaction);
 
@@ -938,6 +1402,7 @@ mod test {
         fn optional_links() {
             let x = DirectiveExpressionParser::new()
                 .parse(
+                    &AccountNames::default(),
                     r#"1970-01-01 * "Payee" "Narration" ^link1 ^link-2
                   Assets:123  -1 CNY
                   Expenses:TestCategory:One 1 CCC @@ 1 CNY"#,
@@ -951,6 +1416,7 @@ mod test {
                 cost: None,
                 single_price: None,
                 total_price: None,
+                comment: None,
             };
             let b = TransactionLine {
                 flag: Flag::Complete,
@@ -962,6 +1428,7 @@ mod test {
                 cost: None,
                 single_price: None,
                 total_price: Some((BigDecimal::from(1i16), "CNY".to_string())),
+                comment: None,
             };
 
             let transaction = Transaction {
@@ -972,6 +1439,8 @@ mod test {
                 tags: vec![],
                 links: vec!["link1".to_owned(), "link-2".to_owned()],
                 lines: vec![a, b],
+                comment: None,
+                location: Location::default(),
             };
             let x1 = Directive::Transaction(transaction);
 
@@ -981,7 +1450,7 @@ mod test {
 
     mod pad {
         use crate::{
-            models::{Account, AccountType, Directive},
+            models::{Account, AccountNames, AccountType, Directive},
             parser::DirectiveExpressionParser,
         };
         use chrono::NaiveDate;
@@ -989,7 +1458,10 @@ mod test {
         #[test]
         fn pad_directive() {
             let x = DirectiveExpressionParser::new()
-                .parse("1970-01-01 pad Assets:123:234:English:中文:日本語:한국어 Equity:ABC")
+                .parse(
+                    &AccountNames::default(),
+                    "1970-01-01 pad Assets:123:234:English:中文:日本語:한국어 Equity:ABC",
+                )
                 .unwrap();
             let directive = Directive::Pad {
                 date: NaiveDate::from_ymd(1970, 1, 1),
@@ -1005,6 +1477,7 @@ mod test {
                     ],
                 ),
                 to: Account::new(AccountType::Equity, vec!["ABC".to_owned()]),
+                comment: None,
             };
 
             assert_eq!(directive, x);
@@ -1013,7 +1486,7 @@ mod test {
 
     mod balance {
         use crate::{
-            models::{Account, AccountType, Directive},
+            models::{Account, AccountNames, AccountType, Directive},
             parser::DirectiveExpressionParser,
         };
         use bigdecimal::BigDecimal;
@@ -1022,7 +1495,10 @@ mod test {
         #[test]
         fn balance_directive() {
             let x = DirectiveExpressionParser::new()
-                .parse("1970-01-01 balance Assets:123:234:English:中文:日本語:한국어  1 CNY")
+                .parse(
+                    &AccountNames::default(),
+                    "1970-01-01 balance Assets:123:234:English:中文:日本語:한국어  1 CNY",
+                )
                 .unwrap();
             let directive = Directive::Balance {
                 date: NaiveDate::from_ymd(1970, 1, 1),
@@ -1038,6 +1514,7 @@ mod test {
                     ],
                 ),
                 amount: (BigDecimal::from(1i16), "CNY".to_owned()),
+                comment: None,
             };
 
             assert_eq!(directive, x);
@@ -1046,7 +1523,7 @@ mod test {
 
     mod document {
         use crate::{
-            models::{Account, AccountType, Directive},
+            models::{Account, AccountNames, AccountType, Directive},
             parser::DirectiveExpressionParser,
         };
         use chrono::NaiveDate;
@@ -1054,12 +1531,16 @@ mod test {
         #[test]
         fn empty_string() {
             let x = DirectiveExpressionParser::new()
-                .parse(r#"1970-01-01 document Assets:123 """#)
+                .parse(
+                    &AccountNames::default(),
+                    r#"1970-01-01 document Assets:123 """#,
+                )
                 .unwrap();
             let directive = Directive::Document {
                 date: NaiveDate::from_ymd(1970, 1, 1),
                 account: Account::new(AccountType::Assets, vec!["123".to_owned()]),
                 path: "".to_owned(),
+                comment: None,
             };
 
             assert_eq!(directive, x);
@@ -1068,12 +1549,16 @@ mod test {
         #[test]
         fn has_document_content() {
             let x = DirectiveExpressionParser::new()
-                .parse(r#"1970-01-01 document Assets:123 "here I am""#)
+                .parse(
+                    &AccountNames::default(),
+                    r#"1970-01-01 document Assets:123 "here I am""#,
+                )
                 .unwrap();
             let directive = Directive::Document {
                 date: NaiveDate::from_ymd(1970, 1, 1),
                 account: Account::new(AccountType::Assets, vec!["123".to_owned()]),
                 path: "here I am".to_owned(),
+                comment: None,
             };
 
             assert_eq!(directive, x);
@@ -1081,19 +1566,23 @@ mod test {
     }
 
     mod price {
-        use crate::{models::Directive, parser::DirectiveExpressionParser};
+        use crate::{
+            models::{AccountNames, Directive},
+            parser::DirectiveExpressionParser,
+        };
         use bigdecimal::BigDecimal;
         use chrono::NaiveDate;
 
         #[test]
         fn test() {
             let x = DirectiveExpressionParser::new()
-                .parse(r#"1970-01-01 price USD   7 CNY"#)
+                .parse(&AccountNames::default(), r#"1970-01-01 price USD   7 CNY"#)
                 .unwrap();
             let directive = Directive::Price {
                 date: NaiveDate::from_ymd(1970, 1, 1),
                 commodity: "USD".to_owned(),
                 amount: (BigDecimal::from(7i16), "CNY".to_owned()),
+                comment: None,
             };
 
             assert_eq!(directive, x);
@@ -1101,18 +1590,25 @@ mod test {
     }
 
     mod event {
-        use crate::{models::Directive, parser::DirectiveExpressionParser};
+        use crate::{
+            models::{AccountNames, Directive},
+            parser::DirectiveExpressionParser,
+        };
         use chrono::NaiveDate;
 
         #[test]
         fn test() {
             let x = DirectiveExpressionParser::new()
-                .parse(r#"1970-01-01 event "location"  "China""#)
+                .parse(
+                    &AccountNames::default(),
+                    r#"1970-01-01 event "location"  "China""#,
+                )
                 .unwrap();
             let directive = Directive::Event {
                 date: NaiveDate::from_ymd(1970, 1, 1),
                 name: "location".to_owned(),
                 value: "China".to_owned(),
+                comment: None,
             };
 
             assert_eq!(directive, x);
@@ -1120,16 +1616,20 @@ mod test {
     }
 
     mod option {
-        use crate::{models::Directive, parser::DirectiveExpressionParser};
+        use crate::{
+            models::{AccountNames, Directive},
+            parser::DirectiveExpressionParser,
+        };
 
         #[test]
         fn test() {
             let x = DirectiveExpressionParser::new()
-                .parse(r#"option "title"  "Personal""#)
+                .parse(&AccountNames::default(), r#"option "title"  "Personal""#)
                 .unwrap();
             let directive = Directive::Option {
                 key: "title".to_owned(),
                 value: "Personal".to_owned(),
+                comment: None,
             };
 
             assert_eq!(directive, x);
@@ -1137,16 +1637,23 @@ mod test {
     }
 
     mod plugin {
-        use crate::{models::Directive, parser::DirectiveExpressionParser};
+        use crate::{
+            models::{AccountNames, Directive},
+            parser::DirectiveExpressionParser,
+        };
 
         #[test]
         fn has_plugin_data() {
             let x = DirectiveExpressionParser::new()
-                .parse(r#"plugin "module name"  "config data""#)
+                .parse(
+                    &AccountNames::default(),
+                    r#"plugin "module name"  "config data""#,
+                )
                 .unwrap();
             let directive = Directive::Plugin {
                 module: "module name".to_owned(),
                 value: Some("config data".to_owned()),
+                comment: None,
             };
 
             assert_eq!(directive, x);
@@ -1155,11 +1662,12 @@ mod test {
         #[test]
         fn do_not_has_plugin_config_data() {
             let x = DirectiveExpressionParser::new()
-                .parse(r#"plugin "module name""#)
+                .parse(&AccountNames::default(), r#"plugin "module name""#)
                 .unwrap();
             let directive = Directive::Plugin {
                 module: "module name".to_owned(),
                 value: None,
+                comment: None,
             };
 
             assert_eq!(directive, x);
@@ -1167,15 +1675,19 @@ mod test {
     }
 
     mod include {
-        use crate::{models::Directive, parser::DirectiveExpressionParser};
+        use crate::{
+            models::{AccountNames, Directive},
+            parser::DirectiveExpressionParser,
+        };
 
         #[test]
         fn has_plugin_data() {
             let x = DirectiveExpressionParser::new()
-                .parse(r#"include "file path""#)
+                .parse(&AccountNames::default(), r#"include "file path""#)
                 .unwrap();
             let directive = Directive::Include {
                 file: "file path".to_owned(),
+                comment: None,
             };
 
             assert_eq!(directive, x);
@@ -1183,34 +1695,72 @@ mod test {
     }
 
     mod custom {
-        use crate::{models::Directive, parser::DirectiveExpressionParser};
+        use crate::{
+            models::{Account, AccountNames, CustomValue, Directive},
+            parser::DirectiveExpressionParser,
+        };
+        use bigdecimal::BigDecimal;
         use chrono::NaiveDate;
+        use std::str::FromStr;
 
         #[test]
         fn custom() {
             let x = DirectiveExpressionParser::new()
-                .parse(r#"1970-01-01 custom "budget" Expenses:Eat "monthly" CNY"#)
+                .parse(
+                    &AccountNames::default(),
+                    r#"1970-01-01 custom "budget" Expenses:Eat "monthly" CNY"#,
+                )
                 .unwrap();
             let directive = Directive::Custom {
                 date: NaiveDate::from_ymd(1970, 1, 1),
                 type_name: "budget".to_owned(),
                 values: vec![
-                    "Expenses:Eat".to_owned(),
-                    "monthly".to_owned(),
-                    "CNY".to_owned(),
+                    CustomValue::Account(Account::from_str("Expenses:Eat").unwrap()),
+                    CustomValue::String("monthly".to_owned()),
+                    CustomValue::Word("CNY".to_owned()),
                 ],
+                comment: None,
             };
 
             assert_eq!(directive, x);
         }
+
+        #[test]
+        fn custom_with_amount() {
+            let x = DirectiveExpressionParser::new()
+                .parse(
+                    &AccountNames::default(),
+                    r#"1970-01-01 custom "budget" Expenses:Eat "monthly" 1000.50 CNY"#,
+                )
+                .unwrap();
+            match x {
+                Directive::Custom { values, .. } => assert_eq!(
+                    vec![
+                        CustomValue::Account(Account::from_str("Expenses:Eat").unwrap()),
+                        CustomValue::String("monthly".to_owned()),
+                        CustomValue::Amount((
+                            BigDecimal::from_str("1000.50").unwrap(),
+                            "CNY".to_owned()
+                        )),
+                    ],
+                    values
+                ),
+                _ => unreachable!(),
+            }
+        }
     }
 
     mod comment {
-        use crate::{models::Directive, parser::DirectiveExpressionParser};
+        use crate::{
+            models::{AccountNames, Directive},
+            parser::DirectiveExpressionParser,
+        };
 
         #[test]
         fn comma() {
-            let x = DirectiveExpressionParser::new().parse(";你好啊").unwrap();
+            let x = DirectiveExpressionParser::new()
+                .parse(&AccountNames::default(), ";你好啊")
+                .unwrap();
             let directive = Directive::Comment {
                 content: ";你好啊".to_owned(),
             };
@@ -1220,7 +1770,7 @@ mod test {
 
     mod entry {
         use crate::{
-            models::{Account, AccountType, Directive},
+            models::{Account, AccountNames, AccountType, Directive},
             parser::EntryParser,
         };
         use chrono::NaiveDate;
@@ -1229,7 +1779,9 @@ mod test {
         fn conbine_test() {
             let content: String = vec!["\n\n;你好啊", "1970-01-01 open Assets:Book\n"].join("\n");
 
-            let entry = EntryParser::new().parse(&content).unwrap();
+            let entry = EntryParser::new()
+                .parse(&AccountNames::default(), &content)
+                .unwrap();
 
             let directives = vec![
                 Directive::Comment {
@@ -1239,9 +1791,11 @@ mod test {
                     date: NaiveDate::from_ymd(1970, 1, 1),
                     account: Account {
                         account_type: AccountType::Assets,
+                        root: "Assets".to_owned(),
                         value: vec!["Book".to_owned()],
                     },
                     commodities: None,
+                    comment: None,
                 },
             ];
 
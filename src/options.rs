@@ -0,0 +1,308 @@
+use crate::{
+    error::BeanCountError,
+    models::{Account, AccountNames, AccountType, Directive},
+};
+use bigdecimal::BigDecimal;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use strum_macros::EnumString;
+
+#[derive(
+    Debug, EnumString, PartialEq, Clone, Copy, strum_macros::Display, Deserialize, Serialize,
+)]
+pub enum BookingMethod {
+    #[strum(serialize = "STRICT")]
+    Strict,
+    #[strum(serialize = "NONE")]
+    None,
+    #[strum(serialize = "AVERAGE")]
+    Average,
+    #[strum(serialize = "FIFO")]
+    Fifo,
+    #[strum(serialize = "LIFO")]
+    Lifo,
+    #[strum(serialize = "HIFO")]
+    Hifo,
+}
+
+#[derive(
+    Debug, EnumString, PartialEq, Clone, Copy, strum_macros::Display, Deserialize, Serialize,
+)]
+pub enum PluginProcessingMode {
+    #[strum(serialize = "default")]
+    Default,
+    #[strum(serialize = "raw")]
+    Raw,
+}
+
+/// typed values of known `option` directives
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+pub struct Options {
+    pub title: String,
+    pub account_names: AccountNames,
+    pub account_previous_balances: String,
+    pub account_previous_earnings: String,
+    pub account_previous_conversions: String,
+    pub account_current_earnings: String,
+    pub account_current_conversions: String,
+    pub account_unrealized_gains: String,
+    pub account_rounding: Option<String>,
+    pub conversion_currency: String,
+    pub inferred_tolerance_default: IndexMap<String, BigDecimal>,
+    pub inferred_tolerance_multiplier: BigDecimal,
+    pub infer_tolerance_from_cost: bool,
+    pub documents: Vec<String>,
+    pub operating_currency: Vec<String>,
+    pub render_commas: bool,
+    pub plugin_processing_mode: PluginProcessingMode,
+    pub long_string_maxlines: usize,
+    pub booking_method: BookingMethod,
+    pub insert_pythonpath: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            title: "Beancount".to_owned(),
+            account_names: AccountNames::default(),
+            account_previous_balances: "Opening-Balances".to_owned(),
+            account_previous_earnings: "Earnings:Previous".to_owned(),
+            account_previous_conversions: "Conversions:Previous".to_owned(),
+            account_current_earnings: "Earnings:Current".to_owned(),
+            account_current_conversions: "Conversions:Current".to_owned(),
+            account_unrealized_gains: "Earnings:Unrealized".to_owned(),
+            account_rounding: None,
+            conversion_currency: "NOTHING".to_owned(),
+            inferred_tolerance_default: IndexMap::new(),
+            inferred_tolerance_multiplier: BigDecimal::from_str("0.5").unwrap(),
+            infer_tolerance_from_cost: false,
+            documents: vec![],
+            operating_currency: vec![],
+            render_commas: false,
+            plugin_processing_mode: PluginProcessingMode::Default,
+            long_string_maxlines: 64,
+            booking_method: BookingMethod::Strict,
+            insert_pythonpath: false,
+        }
+    }
+}
+
+impl Options {
+    /// collect options from `option` directives, invalid ones are reported and skipped
+    pub fn from_directives(directives: &[Directive]) -> (Options, Vec<BeanCountError>) {
+        let mut options = Options::default();
+        let errors = directives
+            .iter()
+            .filter_map(|directive| match directive {
+                Directive::Option { key, value, .. } => options.set(key, value).err(),
+                _ => None,
+            })
+            .collect();
+        (options, errors)
+    }
+
+    /// set option by its key, repeatable options like `operating_currency` are appended
+    /// ```rust
+    /// use beancount::options::{BookingMethod, Options};
+    /// let mut options = Options::default();
+    /// options.set("booking_method", "FIFO").unwrap();
+    /// options.set("operating_currency", "USD").unwrap();
+    /// options.set("operating_currency", "CNY").unwrap();
+    /// assert_eq!(BookingMethod::Fifo, options.booking_method);
+    /// assert_eq!(vec!["USD", "CNY"], options.operating_currency);
+    /// assert!(options.set("unknown", "value").is_err());
+    /// assert!(options.set("render_commas", "maybe").is_err());
+    /// ```
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), BeanCountError> {
+        let invalid = || BeanCountError::InvalidOption(key.to_owned(), value.to_owned());
+        if self.account_names.set_option(key, value)? {
+            return Ok(());
+        }
+        match key {
+            "title" => self.title = value.to_owned(),
+            "account_previous_balances" => {
+                self.account_previous_balances = sub_account(value).ok_or_else(invalid)?
+            }
+            "account_previous_earnings" => {
+                self.account_previous_earnings = sub_account(value).ok_or_else(invalid)?
+            }
+            "account_previous_conversions" => {
+                self.account_previous_conversions = sub_account(value).ok_or_else(invalid)?
+            }
+            "account_current_earnings" => {
+                self.account_current_earnings = sub_account(value).ok_or_else(invalid)?
+            }
+            "account_current_conversions" => {
+                self.account_current_conversions = sub_account(value).ok_or_else(invalid)?
+            }
+            "account_unrealized_gains" => {
+                self.account_unrealized_gains = sub_account(value).ok_or_else(invalid)?
+            }
+            "account_rounding" => {
+                self.account_rounding = Some(sub_account(value).ok_or_else(invalid)?)
+            }
+            "conversion_currency" => self.conversion_currency = value.to_owned(),
+            "inferred_tolerance_default" => {
+                let mut parts = value.splitn(2, ':');
+                let currency = parts.next().filter(|c| !c.is_empty()).ok_or_else(invalid)?;
+                let tolerance = parts
+                    .next()
+                    .and_then(|t| BigDecimal::from_str(t).ok())
+                    .ok_or_else(invalid)?;
+                self.inferred_tolerance_default
+                    .insert(currency.to_owned(), tolerance);
+            }
+            "inferred_tolerance_multiplier" => {
+                self.inferred_tolerance_multiplier =
+                    BigDecimal::from_str(value).map_err(|_| invalid())?
+            }
+            "infer_tolerance_from_cost" => {
+                self.infer_tolerance_from_cost = parse_bool(value).ok_or_else(invalid)?
+            }
+            "documents" => self.documents.push(value.to_owned()),
+            "operating_currency" => self.operating_currency.push(value.to_owned()),
+            "render_commas" => self.render_commas = parse_bool(value).ok_or_else(invalid)?,
+            "plugin_processing_mode" => {
+                self.plugin_processing_mode =
+                    PluginProcessingMode::from_str(value).map_err(|_| invalid())?
+            }
+            "long_string_maxlines" => {
+                self.long_string_maxlines = value.parse().map_err(|_| invalid())?
+            }
+            "booking_method" => {
+                self.booking_method = BookingMethod::from_str(value).map_err(|_| invalid())?
+            }
+            "insert_pythonpath" => {
+                self.insert_pythonpath = parse_bool(value).ok_or_else(invalid)?
+            }
+            _ => return Err(BeanCountError::UnknownOption(key.to_owned())),
+        };
+        Ok(())
+    }
+
+    /// the full account of an equity sub account option like `account_current_earnings`
+    /// ```rust
+    /// use beancount::options::Options;
+    /// let options = Options::default();
+    /// assert_eq!(
+    ///     "Equity:Earnings:Current",
+    ///     options.equity_account(&options.account_current_earnings).to_string()
+    /// );
+    /// ```
+    pub fn equity_account(&self, sub_account: &str) -> Account {
+        self.account_names.account(
+            AccountType::Equity,
+            sub_account.split(':').map(|s| s.to_owned()).collect(),
+        )
+    }
+
+    /// the tolerance of currency when it cannot be inferred from numbers
+    pub fn default_tolerance(&self, currency: &str) -> BigDecimal {
+        self.inferred_tolerance_default
+            .get(currency)
+            .or_else(|| self.inferred_tolerance_default.get("*"))
+            .cloned()
+            .unwrap_or_else(|| BigDecimal::from(0))
+    }
+}
+
+fn sub_account(value: &str) -> Option<String> {
+    if value.split(':').all(|part| !part.is_empty()) {
+        Some(value.to_owned())
+    } else {
+        None
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "true" | "1" => Some(true),
+        "false" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        error::BeanCountError,
+        models::{AccountType, Directive},
+        options::{BookingMethod, Options},
+    };
+    use bigdecimal::BigDecimal;
+    use std::str::FromStr;
+
+    fn option(key: &str, value: &str) -> Directive {
+        Directive::Option {
+            key: key.to_owned(),
+            value: value.to_owned(),
+            comment: None,
+        }
+    }
+
+    #[test]
+    fn from_directives() {
+        let (options, errors) = Options::from_directives(&[
+            option("title", "Example Beancount file"),
+            option("operating_currency", "USD"),
+            option("operating_currency", "CNY"),
+            option("name_assets", "Aktiva"),
+            option("booking_method", "AVERAGE"),
+            option("render_commas", "TRUE"),
+            option("inferred_tolerance_default", "USD:0.005"),
+            option("account_previous_balances", "Opening"),
+        ]);
+
+        assert!(errors.is_empty());
+        assert_eq!("Example Beancount file", options.title);
+        assert_eq!(vec!["USD", "CNY"], options.operating_currency);
+        assert_eq!(
+            Some(AccountType::Assets),
+            options.account_names.account_type("Aktiva")
+        );
+        assert_eq!(BookingMethod::Average, options.booking_method);
+        assert!(options.render_commas);
+        assert_eq!(
+            BigDecimal::from_str("0.005").unwrap(),
+            options.default_tolerance("USD")
+        );
+        assert_eq!(BigDecimal::from(0), options.default_tolerance("CNY"));
+        assert_eq!("Opening", options.account_previous_balances);
+    }
+
+    #[test]
+    fn invalid_options() {
+        let (options, errors) = Options::from_directives(&[
+            option("not_an_option", "value"),
+            option("booking_method", "RANDOM"),
+            option("inferred_tolerance_default", "0.005"),
+            option("long_string_maxlines", "-1"),
+            option("account_current_earnings", "Earnings::Current"),
+            option("name_income", "income"),
+        ]);
+
+        assert_eq!(
+            vec![
+                BeanCountError::UnknownOption("not_an_option".to_owned()),
+                BeanCountError::InvalidOption("booking_method".to_owned(), "RANDOM".to_owned()),
+                BeanCountError::InvalidOption(
+                    "inferred_tolerance_default".to_owned(),
+                    "0.005".to_owned()
+                ),
+                BeanCountError::InvalidOption("long_string_maxlines".to_owned(), "-1".to_owned()),
+                BeanCountError::InvalidOption(
+                    "account_current_earnings".to_owned(),
+                    "Earnings::Current".to_owned()
+                ),
+                BeanCountError::InvalidOption("name_income".to_owned(), "income".to_owned()),
+            ],
+            errors
+        );
+        assert_eq!(BookingMethod::Strict, options.booking_method);
+        assert_eq!(
+            Some(AccountType::Income),
+            options.account_names.account_type("Income")
+        );
+    }
+}
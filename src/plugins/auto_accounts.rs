@@ -0,0 +1,108 @@
+use crate::{
+    error::BeanCountError,
+    models::{sort_directives, Account, Directive},
+    options::Options,
+    plugins::Plugin,
+};
+use chrono::NaiveDate;
+use std::collections::{BTreeMap, HashSet};
+
+/// `beancount.plugins.auto_accounts`, open accounts used without `open` at their first use
+pub struct AutoAccounts;
+
+impl Plugin for AutoAccounts {
+    fn process(
+        &self,
+        entries: Vec<Directive>,
+        _options: &Options,
+        _config: Option<&str>,
+    ) -> (Vec<Directive>, Vec<BeanCountError>) {
+        let opened: HashSet<&Account> = entries
+            .iter()
+            .filter_map(|directive| match directive {
+                Directive::Open { account, .. } => Some(account),
+                _ => None,
+            })
+            .collect();
+        let mut first_use: BTreeMap<&Account, NaiveDate> = BTreeMap::new();
+        for directive in &entries {
+            if let Some(date) = directive.date() {
+                for account in directive.accounts() {
+                    if !opened.contains(account) {
+                        let first = first_use.entry(account).or_insert(*date);
+                        *first = (*first).min(*date);
+                    }
+                }
+            }
+        }
+        let opens: Vec<Directive> = first_use
+            .into_iter()
+            .map(|(account, date)| Directive::Open {
+                date,
+                account: account.clone(),
+                commodities: None,
+                comment: None,
+            })
+            .collect();
+
+        let mut entries = entries;
+        entries.extend(opens);
+        sort_directives(&mut entries);
+        (entries, vec![])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        loader::load,
+        models::{Account, Directive},
+    };
+    use chrono::NaiveDate;
+    use std::str::FromStr;
+
+    #[test]
+    fn open_at_first_use() {
+        let content = r#"plugin "beancount.plugins.auto_accounts"
+1970-01-01 open Assets:Bank
+1970-03-01 * "Lunch"
+  Assets:Bank -10 USD
+  Expenses:Food
+1970-02-01 balance Assets:Cash 0 USD
+1970-04-01 * "Dinner"
+  Assets:Cash -10 USD
+  Expenses:Food
+"#;
+        let ledger = load(content).unwrap();
+        assert!(ledger.errors.is_empty());
+        let opens: Vec<(NaiveDate, String)> = ledger
+            .directives
+            .iter()
+            .filter_map(|directive| match directive {
+                Directive::Open { date, account, .. } => Some((*date, account.to_string())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                (
+                    NaiveDate::from_ymd_opt(1970, 1, 1).unwrap(),
+                    "Assets:Bank".to_owned()
+                ),
+                (
+                    NaiveDate::from_ymd_opt(1970, 2, 1).unwrap(),
+                    "Assets:Cash".to_owned()
+                ),
+                (
+                    NaiveDate::from_ymd_opt(1970, 3, 1).unwrap(),
+                    "Expenses:Food".to_owned()
+                ),
+            ],
+            opens
+        );
+        assert!(matches!(
+            &ledger.directives[3],
+            Directive::Balance { account, .. } if account == &Account::from_str("Assets:Cash").unwrap()
+        ));
+    }
+}
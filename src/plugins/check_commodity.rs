@@ -0,0 +1,83 @@
+use crate::{error::BeanCountError, models::Directive, options::Options, plugins::Plugin};
+use indexmap::IndexSet;
+use std::collections::HashSet;
+
+/// `beancount.plugins.check_commodity`, report currencies used without `commodity` directive
+///
+/// Currencies of postings, prices and balances are checked, each one is reported once.
+pub struct CheckCommodity;
+
+impl Plugin for CheckCommodity {
+    fn process(
+        &self,
+        entries: Vec<Directive>,
+        _options: &Options,
+        _config: Option<&str>,
+    ) -> (Vec<Directive>, Vec<BeanCountError>) {
+        let declared: HashSet<&String> = entries
+            .iter()
+            .filter_map(|directive| match directive {
+                Directive::Commodity { name, .. } => Some(name),
+                _ => None,
+            })
+            .collect();
+        let mut used: IndexSet<&String> = IndexSet::new();
+        for directive in &entries {
+            match directive {
+                Directive::Transaction(transaction) => {
+                    for line in &transaction.lines {
+                        let amounts = [
+                            line.amount.as_ref(),
+                            line.cost.as_ref().map(|(cost, _)| cost),
+                            line.single_price.as_ref(),
+                            line.total_price.as_ref(),
+                        ];
+                        used.extend(amounts.iter().flatten().map(|amount| &amount.1));
+                    }
+                }
+                Directive::Price {
+                    commodity, amount, ..
+                } => {
+                    used.insert(commodity);
+                    used.insert(&amount.1);
+                }
+                Directive::Balance { amount, .. } => {
+                    used.insert(&amount.1);
+                }
+                _ => {}
+            }
+        }
+        let errors = used
+            .into_iter()
+            .filter(|currency| !declared.contains(currency))
+            .map(|currency| BeanCountError::UndeclaredCommodity(currency.clone()))
+            .collect();
+        (entries, errors)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{error::BeanCountError, loader::load};
+
+    #[test]
+    fn report_undeclared_once() {
+        let content = r#"plugin "beancount.plugins.check_commodity"
+1970-01-01 commodity USD
+1970-01-02 * "Buy"
+  Assets:Broker 2 HOOL {100 USD} @ 110 EUR
+  Assets:Bank -200 USD
+1970-01-03 price HOOL 120 USD
+1970-01-04 balance Assets:Bank 10 CNY
+"#;
+        let ledger = load(content).unwrap();
+        assert_eq!(
+            vec![
+                BeanCountError::UndeclaredCommodity("HOOL".to_owned()),
+                BeanCountError::UndeclaredCommodity("EUR".to_owned()),
+                BeanCountError::UndeclaredCommodity("CNY".to_owned()),
+            ],
+            ledger.errors
+        );
+    }
+}
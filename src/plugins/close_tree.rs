@@ -0,0 +1,89 @@
+use crate::{
+    error::BeanCountError,
+    models::{Account, Directive},
+    options::Options,
+    plugins::Plugin,
+};
+use std::collections::HashSet;
+
+/// `beancount.plugins.close_tree`, close opened descendants of a closed account with it
+///
+/// The inserted `close` directives follow the one of the parent, descendants closed by their
+/// own `close` directive are left alone.
+pub struct CloseTree;
+
+impl Plugin for CloseTree {
+    fn process(
+        &self,
+        entries: Vec<Directive>,
+        _options: &Options,
+        _config: Option<&str>,
+    ) -> (Vec<Directive>, Vec<BeanCountError>) {
+        let mut opened: Vec<Account> = vec![];
+        let mut closed: HashSet<Account> = HashSet::new();
+        for directive in &entries {
+            match directive {
+                Directive::Open { account, .. } if !opened.contains(account) => {
+                    opened.push(account.clone())
+                }
+                Directive::Close { account, .. } => {
+                    closed.insert(account.clone());
+                }
+                _ => {}
+            }
+        }
+
+        let mut processed = Vec::with_capacity(entries.len());
+        for directive in entries {
+            let mut descendants = vec![];
+            if let Directive::Close { date, account, .. } = &directive {
+                for child in &opened {
+                    if child != account && account.contains(child) && closed.insert(child.clone()) {
+                        descendants.push(Directive::Close {
+                            date: *date,
+                            account: child.clone(),
+                            comment: None,
+                        });
+                    }
+                }
+            }
+            processed.push(directive);
+            processed.extend(descendants);
+        }
+        (processed, vec![])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{loader::load, models::Directive, to_file::ToBeancountFile};
+
+    #[test]
+    fn close_descendants() {
+        let content = r#"plugin "beancount.plugins.close_tree"
+2020-01-01 open Assets:OldBank
+2020-01-01 open Assets:OldBank:Checking
+2020-01-01 open Assets:OldBank:Savings
+2020-01-01 open Assets:OldBank:Savings:Bonus
+2020-01-01 open Assets:OldBankrupt
+2020-03-01 close Assets:OldBank:Savings:Bonus
+2020-06-01 close Assets:OldBank
+"#;
+        let ledger = load(content).unwrap();
+        let closes: Vec<String> = ledger
+            .directives
+            .iter()
+            .filter(|directive| matches!(directive, Directive::Close { .. }))
+            .map(|directive| directive.to_text())
+            .collect();
+        assert_eq!(
+            vec![
+                "2020-03-01 close Assets:OldBank:Savings:Bonus",
+                "2020-06-01 close Assets:OldBank",
+                "2020-06-01 close Assets:OldBank:Checking",
+                "2020-06-01 close Assets:OldBank:Savings",
+            ],
+            closes
+        );
+    }
+}
@@ -0,0 +1,213 @@
+use crate::{
+    error::BeanCountError,
+    models::{Directive, Flag, Transaction},
+    options::Options,
+    plugins::Plugin,
+};
+use chrono::{Datelike, Duration, Months, NaiveDate};
+use regex::Regex;
+
+/// tag marking a transaction as forecast, as an alternative of flag `#`
+pub const FORECAST_TAG: &str = "forecast";
+
+/// `beancount.plugins.forecast`, expand recurring forecast transactions
+///
+/// A transaction flagged `#` or tagged `FORECAST_TAG` whose narration ends with a rule like
+/// `[MONTHLY]`, `[WEEKLY SKIP 1 TIME REPEAT 4 TIMES]` or `[YEARLY UNTIL 2026-12-31]` is
+/// replaced by one transaction flagged `#` per occurrence, starting at its date. Without
+/// `REPEAT` or `UNTIL`, occurrences go until the end of the year of the transaction.
+///
+/// Metadata of transactions is not kept by the parser, so neither the marker nor the rule can
+/// be given as metadata.
+/// ```rust
+/// use beancount::loader::load;
+/// let ledger = load(
+///     "plugin \"beancount.plugins.forecast\"\n2026-10-31 # \"Rent [MONTHLY]\"\n  Assets:Bank -1000 USD\n  Expenses:Rent\n",
+/// ).unwrap();
+/// let dates: Vec<String> = ledger.directives.iter().filter_map(|d| d.date()).map(|d| d.to_string()).collect();
+/// assert_eq!(vec!["2026-10-31", "2026-11-30", "2026-12-31"], dates);
+/// ```
+pub struct Forecast;
+
+impl Plugin for Forecast {
+    fn process(
+        &self,
+        entries: Vec<Directive>,
+        _options: &Options,
+        _config: Option<&str>,
+    ) -> (Vec<Directive>, Vec<BeanCountError>) {
+        let rule = Regex::new(
+            r"^(.*?)\s*\[(DAILY|WEEKLY|MONTHLY|YEARLY)(?:\s+SKIP\s+(\d+)\s+TIMES?)?(?:\s+REPEAT\s+(\d+)\s+TIMES?)?(?:\s+UNTIL\s+(\d{4}-\d{1,2}-\d{1,2}))?\]$",
+        )
+        .expect("regex of recurrence rule should be valid");
+
+        let mut errors = vec![];
+        let mut processed = Vec::with_capacity(entries.len());
+        for directive in entries {
+            let transaction = match &directive {
+                Directive::Transaction(transaction)
+                    if transaction.flag == Flag::Forecast
+                        || transaction.tags.iter().any(|tag| tag == FORECAST_TAG) =>
+                {
+                    transaction
+                }
+                _ => {
+                    processed.push(directive);
+                    continue;
+                }
+            };
+            let narration = transaction.narration.as_deref().unwrap_or_default();
+            let captures = match rule.captures(narration) {
+                Some(captures) => captures,
+                None => {
+                    processed.push(directive);
+                    continue;
+                }
+            };
+            let until = match captures.get(5) {
+                Some(until) => match NaiveDate::parse_from_str(until.as_str(), "%Y-%m-%d") {
+                    Ok(until) => Some(until),
+                    Err(_) => {
+                        errors.push(BeanCountError::InvalidRecurrence(
+                            transaction.date,
+                            narration.to_owned(),
+                        ));
+                        processed.push(directive);
+                        continue;
+                    }
+                },
+                None => None,
+            };
+            let skip = captures.get(3).map(|skip| skip.as_str().parse::<u32>());
+            let count = captures.get(4).map(|count| count.as_str().parse::<usize>());
+            let (skip, count) = match (skip.transpose(), count.transpose()) {
+                (Ok(skip), Ok(count)) => (skip.unwrap_or(0), count),
+                _ => {
+                    errors.push(BeanCountError::InvalidRecurrence(
+                        transaction.date,
+                        narration.to_owned(),
+                    ));
+                    processed.push(directive);
+                    continue;
+                }
+            };
+            let until = match (count, until) {
+                (None, None) => NaiveDate::from_ymd_opt(transaction.date.year(), 12, 31),
+                (_, until) => until,
+            };
+            let dates = occurrences(transaction.date, &captures[2], skip.saturating_add(1))
+                .take_while(|date| until.is_none_or(|until| *date <= until))
+                .take(count.unwrap_or(usize::MAX));
+            let narration = captures[1].to_owned();
+            let expanded: Vec<Directive> = dates
+                .map(|date| {
+                    Directive::Transaction(Transaction {
+                        date,
+                        flag: Flag::Forecast,
+                        narration: Some(narration.clone()),
+                        ..transaction.clone()
+                    })
+                })
+                .collect();
+            processed.extend(expanded);
+        }
+        (processed, errors)
+    }
+}
+
+/// dates from start, every `step` periods
+fn occurrences(start: NaiveDate, period: &str, step: u32) -> impl Iterator<Item = NaiveDate> {
+    let period = period.to_owned();
+    (0u32..).map_while(move |index| {
+        let steps = index.checked_mul(step)?;
+        match period.as_str() {
+            "DAILY" => start.checked_add_signed(Duration::days(steps.into())),
+            "WEEKLY" => start.checked_add_signed(Duration::weeks(steps.into())),
+            "MONTHLY" => start.checked_add_months(Months::new(steps)),
+            _ => start.checked_add_months(Months::new(steps.checked_mul(12)?)),
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{error::BeanCountError, loader::load, models::Directive, to_file::ToBeancountFile};
+    use chrono::NaiveDate;
+
+    fn headers(content: &str) -> Vec<String> {
+        let ledger = load(content).unwrap();
+        assert!(ledger.errors.is_empty());
+        ledger
+            .directives
+            .iter()
+            .filter(|directive| matches!(directive, Directive::Transaction(_)))
+            .map(|directive| directive.to_text().lines().next().unwrap().to_owned())
+            .collect()
+    }
+
+    #[test]
+    fn expand_rules() {
+        let content = r#"plugin "beancount.plugins.forecast"
+2026-01-31 # "Power" "Electricity [MONTHLY UNTIL 2026-04-30]"
+  Assets:Bank -50 USD
+  Expenses:Power
+2026-01-01 # "Gym [WEEKLY SKIP 1 TIME REPEAT 3 TIMES]"
+  Assets:Bank -10 USD
+  Expenses:Gym
+2026-01-01 * "Insurance [YEARLY REPEAT 2 TIMES]" #forecast
+  Assets:Bank -300 USD
+  Expenses:Insurance
+2026-01-02 # "One-off"
+  Assets:Bank -1 USD
+  Expenses:Misc
+"#;
+        assert_eq!(
+            vec![
+                "2026-01-31 # \"Power\" \"Electricity\"",
+                "2026-02-28 # \"Power\" \"Electricity\"",
+                "2026-03-31 # \"Power\" \"Electricity\"",
+                "2026-04-30 # \"Power\" \"Electricity\"",
+                "2026-01-01 # \"Gym\"",
+                "2026-01-15 # \"Gym\"",
+                "2026-01-29 # \"Gym\"",
+                "2026-01-01 # \"Insurance\" #forecast",
+                "2027-01-01 # \"Insurance\" #forecast",
+                "2026-01-02 # \"One-off\"",
+            ],
+            headers(content)
+        );
+    }
+
+    #[test]
+    fn invalid_rule_is_reported() {
+        let content = r#"plugin "beancount.plugins.forecast"
+2026-01-01 # "Gym [WEEKLY REPEAT 99999999999999999999999 TIMES]"
+  Assets:Bank -10 USD
+  Expenses:Gym
+2026-01-01 # "Rent [MONTHLY UNTIL 2026-02-30]"
+  Assets:Bank -1000 USD
+  Expenses:Rent
+"#;
+        let ledger = load(content).unwrap();
+        let date = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap();
+        assert_eq!(
+            vec![
+                BeanCountError::InvalidRecurrence(
+                    date,
+                    "Gym [WEEKLY REPEAT 99999999999999999999999 TIMES]".to_owned()
+                ),
+                BeanCountError::InvalidRecurrence(
+                    date,
+                    "Rent [MONTHLY UNTIL 2026-02-30]".to_owned()
+                ),
+            ],
+            ledger.errors
+        );
+        let kept = ledger
+            .directives
+            .iter()
+            .filter(|directive| matches!(directive, Directive::Transaction(_)))
+            .count();
+        assert_eq!(2, kept);
+    }
+}
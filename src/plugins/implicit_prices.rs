@@ -0,0 +1,107 @@
+use crate::{
+    error::BeanCountError,
+    models::{Amount, Directive},
+    options::Options,
+    plugins::Plugin,
+};
+use bigdecimal::Zero;
+use chrono::NaiveDate;
+use std::collections::HashSet;
+
+/// `beancount.plugins.implicit_prices`, add `price` directives for prices and costs of postings
+///
+/// Price of a posting is taken from `@`, then `@@` divided by units, then `{cost}`. A price
+/// equal to an explicit or already added one on the same day is not added again.
+pub struct ImplicitPrices;
+
+impl Plugin for ImplicitPrices {
+    fn process(
+        &self,
+        entries: Vec<Directive>,
+        _options: &Options,
+        _config: Option<&str>,
+    ) -> (Vec<Directive>, Vec<BeanCountError>) {
+        let mut known: HashSet<(NaiveDate, String, Amount)> = entries
+            .iter()
+            .filter_map(|directive| match directive {
+                Directive::Price {
+                    date,
+                    commodity,
+                    amount,
+                    ..
+                } => Some((*date, commodity.clone(), amount.clone())),
+                _ => None,
+            })
+            .collect();
+
+        let mut processed = Vec::with_capacity(entries.len());
+        for directive in entries {
+            let mut prices = vec![];
+            if let Directive::Transaction(transaction) = &directive {
+                for line in &transaction.lines {
+                    let units = match &line.amount {
+                        Some(units) => units,
+                        None => continue,
+                    };
+                    let price = match (&line.single_price, &line.total_price, &line.cost) {
+                        (Some(price), _, _) => price.clone(),
+                        (None, Some(total), _) if !units.0.is_zero() => {
+                            (&total.0 / units.0.abs(), total.1.clone())
+                        }
+                        (None, None, Some((cost, _))) => cost.clone(),
+                        _ => continue,
+                    };
+                    if known.insert((transaction.date, units.1.clone(), price.clone())) {
+                        prices.push(Directive::Price {
+                            date: transaction.date,
+                            commodity: units.1.clone(),
+                            amount: price,
+                            comment: None,
+                        });
+                    }
+                }
+            }
+            processed.push(directive);
+            processed.extend(prices);
+        }
+        (processed, vec![])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{loader::load, models::Directive, to_file::ToBeancountFile};
+
+    #[test]
+    fn prices_from_postings() {
+        let content = r#"plugin "beancount.plugins.implicit_prices"
+2020-01-01 price HOOL 100 USD
+2020-01-01 * "Buy"
+  Assets:Broker 2 HOOL {100 USD}
+  Assets:Bank
+2020-01-02 * "Buy"
+  Assets:Broker 2 HOOL {101 USD} @ 102 USD
+  Assets:Broker 1 HOOL {101 USD}
+  Assets:Bank
+2020-01-03 * "Exchange"
+  Assets:Bank -500 CNY @@ 100 USD
+  Assets:Bank 100 USD
+"#;
+        let ledger = load(content).unwrap();
+        let prices: Vec<String> = ledger
+            .directives
+            .iter()
+            .filter(|directive| matches!(directive, Directive::Price { .. }))
+            .map(|directive| directive.to_text())
+            .collect();
+        assert_eq!(
+            vec![
+                "2020-01-01 price HOOL 100 USD",
+                "2020-01-02 price HOOL 102 USD",
+                "2020-01-02 price HOOL 101 USD",
+                "2020-01-03 price CNY 0.2 USD",
+            ],
+            prices
+        );
+    }
+}
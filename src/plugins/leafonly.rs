@@ -0,0 +1,89 @@
+use crate::{
+    error::BeanCountError,
+    models::{Account, Directive, Location},
+    options::Options,
+    plugins::Plugin,
+};
+use chrono::NaiveDate;
+use std::collections::{BTreeMap, HashSet};
+
+/// `beancount.plugins.leafonly`, report accounts with children which have postings
+///
+/// Each account is reported once with the date and location of its first posting.
+pub struct LeafOnly;
+
+impl Plugin for LeafOnly {
+    fn process(
+        &self,
+        entries: Vec<Directive>,
+        _options: &Options,
+        _config: Option<&str>,
+    ) -> (Vec<Directive>, Vec<BeanCountError>) {
+        let mut parents: HashSet<Account> = HashSet::new();
+        for account in entries.iter().flat_map(|directive| directive.accounts()) {
+            let mut parent = account.parent();
+            while let Some(account) = parent {
+                parent = account.parent();
+                if !parents.insert(account) {
+                    break;
+                }
+            }
+        }
+        let mut first_posting: BTreeMap<&Account, (NaiveDate, Location)> = BTreeMap::new();
+        for directive in &entries {
+            if let Directive::Transaction(transaction) = directive {
+                for line in &transaction.lines {
+                    if parents.contains(&line.account) {
+                        let first = first_posting
+                            .entry(&line.account)
+                            .or_insert((transaction.date, transaction.location));
+                        if transaction.date < first.0 {
+                            *first = (transaction.date, transaction.location);
+                        }
+                    }
+                }
+            }
+        }
+        let errors = first_posting
+            .into_iter()
+            .map(|(account, (date, location))| {
+                BeanCountError::NonLeafAccount(date, account.to_string(), location)
+            })
+            .collect();
+        (entries, errors)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{error::BeanCountError, loader::load, models::Location};
+    use chrono::NaiveDate;
+
+    #[test]
+    fn postings_to_parent() {
+        let content = r#"plugin "beancount.plugins.leafonly"
+1970-01-01 open Expenses:Food:Lunch:Rice
+1970-01-03 * "Dinner"
+  Assets:Bank -10 USD
+  Expenses:Food
+1970-01-02 * "Snack"
+  Assets:Bank:Card -10 USD
+  Expenses:Food:Lunch
+"#;
+        let ledger = load(content).unwrap();
+        let date = |day| NaiveDate::from_ymd_opt(1970, 1, day).unwrap();
+        let (dinner, snack) = (Location(Some(3)), Location(Some(6)));
+        assert_eq!(
+            vec![
+                BeanCountError::NonLeafAccount(date(3), "Assets:Bank".to_owned(), dinner),
+                BeanCountError::NonLeafAccount(date(3), "Expenses:Food".to_owned(), dinner),
+                BeanCountError::NonLeafAccount(date(2), "Expenses:Food:Lunch".to_owned(), snack),
+            ],
+            ledger.errors
+        );
+        assert_eq!(
+            "account Expenses:Food has children but is posted to on 1970-01-03 at line 3",
+            ledger.errors[1].to_string()
+        );
+    }
+}
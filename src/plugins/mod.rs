@@ -0,0 +1,195 @@
+use crate::{error::BeanCountError, models::Directive, options::Options};
+use std::collections::HashMap;
+
+pub mod auto_accounts;
+pub mod check_commodity;
+pub mod close_tree;
+pub mod forecast;
+pub mod implicit_prices;
+pub mod leafonly;
+pub mod noduplicates;
+pub mod onecommodity;
+pub mod sellgains;
+pub mod split_expenses;
+pub mod unrealized;
+
+/// transformation of directives, enabled by `plugin "module" "config"` in a file
+pub trait Plugin {
+    /// return the transformed directives and errors found in them
+    fn process(
+        &self,
+        entries: Vec<Directive>,
+        options: &Options,
+        config: Option<&str>,
+    ) -> (Vec<Directive>, Vec<BeanCountError>);
+}
+
+impl<F> Plugin for F
+where
+    F: Fn(Vec<Directive>, &Options, Option<&str>) -> (Vec<Directive>, Vec<BeanCountError>),
+{
+    fn process(
+        &self,
+        entries: Vec<Directive>,
+        options: &Options,
+        config: Option<&str>,
+    ) -> (Vec<Directive>, Vec<BeanCountError>) {
+        self(entries, options, config)
+    }
+}
+
+/// plugins by module name
+pub struct PluginRegistry {
+    plugins: HashMap<String, Box<dyn Plugin>>,
+}
+
+impl Default for PluginRegistry {
+    fn default() -> Self {
+        PluginRegistry::new()
+    }
+}
+
+impl PluginRegistry {
+    /// registry with the built-in plugins
+    pub fn new() -> Self {
+        let mut registry = PluginRegistry {
+            plugins: HashMap::new(),
+        };
+        registry.register(
+            "beancount.plugins.auto_accounts",
+            auto_accounts::AutoAccounts,
+        );
+        registry.register(
+            "beancount.plugins.implicit_prices",
+            implicit_prices::ImplicitPrices,
+        );
+        registry.register(
+            "beancount.plugins.check_commodity",
+            check_commodity::CheckCommodity,
+        );
+        registry.register("beancount.plugins.close_tree", close_tree::CloseTree);
+        registry.register("beancount.plugins.forecast", forecast::Forecast);
+        registry.register("beancount.plugins.leafonly", leafonly::LeafOnly);
+        registry.register("beancount.plugins.noduplicates", noduplicates::NoDuplicates);
+        registry.register("beancount.plugins.onecommodity", onecommodity::OneCommodity);
+        registry.register("beancount.plugins.sellgains", sellgains::SellGains);
+        registry.register(
+            "beancount.plugins.split_expenses",
+            split_expenses::SplitExpenses,
+        );
+        registry.register("beancount.plugins.unrealized", unrealized::Unrealized);
+        registry
+    }
+
+    /// register plugin under module name, replacing the plugin registered before
+    pub fn register(&mut self, module: impl Into<String>, plugin: impl Plugin + 'static) {
+        self.plugins.insert(module.into(), Box::new(plugin));
+    }
+
+    pub fn get(&self, module: &str) -> Option<&dyn Plugin> {
+        self.plugins.get(module).map(|plugin| plugin.as_ref())
+    }
+
+    /// run plugins declared by `plugin` directives in order of declaration
+    ///
+    /// Unknown modules are reported as errors and skipped.
+    pub fn run(
+        &self,
+        directives: Vec<Directive>,
+        options: &Options,
+    ) -> (Vec<Directive>, Vec<BeanCountError>) {
+        let declared: Vec<(String, Option<String>)> = directives
+            .iter()
+            .filter_map(|directive| match directive {
+                Directive::Plugin { module, value, .. } => Some((module.clone(), value.clone())),
+                _ => None,
+            })
+            .collect();
+        let mut entries = directives;
+        let mut errors = vec![];
+        for (module, config) in declared {
+            match self.get(&module) {
+                Some(plugin) => {
+                    let (processed, plugin_errors) =
+                        plugin.process(entries, options, config.as_deref());
+                    entries = processed;
+                    errors.extend(plugin_errors);
+                }
+                None => errors.push(BeanCountError::UnknownPlugin(module)),
+            }
+        }
+        (entries, errors)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        error::BeanCountError,
+        loader::load_with_plugins,
+        models::Directive,
+        options::Options,
+        plugins::{Plugin, PluginRegistry},
+    };
+    use std::{cell::RefCell, rc::Rc};
+
+    /// keep directives of the type given by config
+    struct Only;
+
+    impl Plugin for Only {
+        fn process(
+            &self,
+            entries: Vec<Directive>,
+            _options: &Options,
+            config: Option<&str>,
+        ) -> (Vec<Directive>, Vec<BeanCountError>) {
+            let entries = entries
+                .into_iter()
+                .filter(|directive| match (directive, config) {
+                    (Directive::Open { .. }, Some("open")) => true,
+                    (Directive::Close { .. }, Some("close")) => true,
+                    (_, _) => false,
+                })
+                .collect();
+            (entries, vec![])
+        }
+    }
+
+    #[test]
+    fn run_in_declared_order() {
+        let calls = Rc::new(RefCell::new(vec![]));
+        let mut registry = PluginRegistry::new();
+        registry.register("only", Only);
+        let recorded = Rc::clone(&calls);
+        registry.register(
+            "record",
+            move |entries: Vec<Directive>, _: &Options, config: Option<&str>| {
+                recorded
+                    .borrow_mut()
+                    .push((config.map(|c| c.to_owned()), entries.len()));
+                (entries, vec![])
+            },
+        );
+        let content = "plugin \"record\" \"first\"\n\
+                       plugin \"only\" \"close\"\n\
+                       plugin \"record\" \"second\"\n\
+                       plugin \"missing\"\n\
+                       1970-01-01 open Assets:Bank\n\
+                       1970-02-01 close Assets:Bank\n";
+        let ledger = load_with_plugins(content, &registry).unwrap();
+        let (directives, errors) = (ledger.directives, ledger.errors);
+        assert_eq!(1, directives.len());
+        assert!(matches!(directives[0], Directive::Close { .. }));
+        assert_eq!(
+            vec![
+                (Some("first".to_owned()), 6),
+                (Some("second".to_owned()), 1)
+            ],
+            *calls.borrow()
+        );
+        assert_eq!(
+            vec![BeanCountError::UnknownPlugin("missing".to_owned())],
+            errors
+        );
+    }
+}
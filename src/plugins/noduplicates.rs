@@ -0,0 +1,159 @@
+use crate::{
+    error::BeanCountError,
+    models::{Account, Amount, Directive, Transaction},
+    options::Options,
+    plugins::Plugin,
+    to_file::ToBeancountFile,
+};
+use chrono::NaiveDate;
+use std::collections::{HashMap, HashSet};
+
+/// `beancount.plugins.noduplicates`, report transactions equal to an earlier one
+///
+/// Transactions are compared by date, flag, payee, narration and postings, comments are
+/// ignored. With config `fuzzy`, postings of the same amount to the same account on the same
+/// day in different transactions are reported as possible duplicates too.
+pub struct NoDuplicates;
+
+type PostingKey = (
+    Account,
+    Option<Amount>,
+    Option<(Amount, Option<String>)>,
+    Option<Amount>,
+    Option<Amount>,
+);
+
+fn transaction_key(
+    transaction: &Transaction,
+) -> (
+    NaiveDate,
+    String,
+    Option<String>,
+    Option<String>,
+    Vec<PostingKey>,
+) {
+    let postings = transaction
+        .lines
+        .iter()
+        .map(|line| {
+            (
+                line.account.clone(),
+                line.amount.clone(),
+                line.cost.clone(),
+                line.single_price.clone(),
+                line.total_price.clone(),
+            )
+        })
+        .collect();
+    (
+        transaction.date,
+        transaction.flag.to_text(),
+        transaction.payee.clone(),
+        transaction.narration.clone(),
+        postings,
+    )
+}
+
+impl Plugin for NoDuplicates {
+    fn process(
+        &self,
+        entries: Vec<Directive>,
+        _options: &Options,
+        config: Option<&str>,
+    ) -> (Vec<Directive>, Vec<BeanCountError>) {
+        let fuzzy = config == Some("fuzzy");
+        let mut seen = HashSet::new();
+        let mut postings: HashMap<(NaiveDate, &Account, &Amount), usize> = HashMap::new();
+        let mut reported = HashSet::new();
+        let mut errors = vec![];
+        for (index, directive) in entries.iter().enumerate() {
+            let transaction = match directive {
+                Directive::Transaction(transaction) => transaction,
+                _ => continue,
+            };
+            if !seen.insert(transaction_key(transaction)) {
+                errors.push(BeanCountError::DuplicateTransaction(
+                    transaction.date,
+                    transaction.narration.clone().unwrap_or_default(),
+                ));
+                continue;
+            }
+            if !fuzzy {
+                continue;
+            }
+            for line in &transaction.lines {
+                let amount = match &line.amount {
+                    Some(amount) => amount,
+                    None => continue,
+                };
+                let key = (transaction.date, &line.account, amount);
+                let first = *postings.entry(key).or_insert(index);
+                if first != index && reported.insert(key) {
+                    errors.push(BeanCountError::PossibleDuplicate(
+                        transaction.date,
+                        line.account.to_string(),
+                        amount.to_text(),
+                    ));
+                }
+            }
+        }
+        (entries, errors)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{error::BeanCountError, loader::load};
+    use chrono::NaiveDate;
+
+    const CONTENT: &str = r#"1970-01-01 * "Shop" "Lunch"
+  Assets:Bank -10 USD
+  Expenses:Food
+1970-01-01 * "Shop" "Lunch" ; imported twice
+  Assets:Bank -10 USD ; card
+  Expenses:Food
+1970-01-01 * "SHOP INC" "Card payment"
+  Assets:Bank -10 USD
+  Expenses:Food
+1970-01-02 * "Shop" "Lunch"
+  Assets:Bank -10 USD
+  Expenses:Food
+"#;
+
+    #[test]
+    fn exact_duplicates() {
+        let ledger = load(&format!(
+            "plugin \"beancount.plugins.noduplicates\"\n{}",
+            CONTENT
+        ))
+        .unwrap();
+        assert_eq!(
+            vec![BeanCountError::DuplicateTransaction(
+                NaiveDate::from_ymd_opt(1970, 1, 1).unwrap(),
+                "Lunch".to_owned()
+            )],
+            ledger.errors
+        );
+    }
+
+    #[test]
+    fn fuzzy_duplicates() {
+        let ledger = load(&format!(
+            "plugin \"beancount.plugins.noduplicates\" \"fuzzy\"\n{}",
+            CONTENT
+        ))
+        .unwrap();
+        let date = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+        assert_eq!(
+            vec![
+                BeanCountError::DuplicateTransaction(date, "Lunch".to_owned()),
+                BeanCountError::PossibleDuplicate(
+                    date,
+                    "Assets:Bank".to_owned(),
+                    "-10 USD".to_owned()
+                ),
+            ],
+            ledger.errors
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use crate::{
+    error::BeanCountError,
+    models::{Account, Directive},
+    options::Options,
+    plugins::Plugin,
+};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+
+/// `beancount.plugins.onecommodity`, report accounts holding units of more than one currency
+///
+/// Accounts opened with more than one allowed currency are skipped, as are accounts matching
+/// the regex given as config. Each account is reported once with the date and location of the
+/// posting bringing the second currency.
+pub struct OneCommodity;
+
+impl Plugin for OneCommodity {
+    fn process(
+        &self,
+        entries: Vec<Directive>,
+        _options: &Options,
+        config: Option<&str>,
+    ) -> (Vec<Directive>, Vec<BeanCountError>) {
+        let skip = match config.map(Regex::new).transpose() {
+            Ok(skip) => skip,
+            Err(_) => {
+                let error = BeanCountError::InvalidPluginConfig(
+                    "beancount.plugins.onecommodity".to_owned(),
+                    config.unwrap_or_default().to_owned(),
+                );
+                return (entries, vec![error]);
+            }
+        };
+        let declared: HashSet<&Account> = entries
+            .iter()
+            .filter_map(|directive| match directive {
+                Directive::Open {
+                    account,
+                    commodities: Some(commodities),
+                    ..
+                } if commodities.len() > 1 => Some(account),
+                _ => None,
+            })
+            .collect();
+
+        let mut transactions: Vec<_> = entries
+            .iter()
+            .filter_map(|directive| match directive {
+                Directive::Transaction(transaction) => Some(transaction),
+                _ => None,
+            })
+            .collect();
+        transactions.sort_by_key(|transaction| transaction.date);
+
+        let mut currencies: HashMap<&Account, &String> = HashMap::new();
+        let mut reported: HashSet<&Account> = HashSet::new();
+        let mut errors = vec![];
+        for transaction in transactions {
+            for line in &transaction.lines {
+                let account = &line.account;
+                let skipped = declared.contains(account)
+                    || skip
+                        .as_ref()
+                        .is_some_and(|skip| skip.is_match(&account.to_string()));
+                let currency = match &line.amount {
+                    Some(amount) if !skipped => &amount.1,
+                    _ => continue,
+                };
+                let first = *currencies.entry(account).or_insert(currency);
+                if first != currency && reported.insert(account) {
+                    errors.push(BeanCountError::MultipleCommodities(
+                        transaction.date,
+                        account.to_string(),
+                        transaction.location,
+                    ));
+                }
+            }
+        }
+        (entries, errors)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{error::BeanCountError, loader::load, models::Location};
+    use chrono::NaiveDate;
+
+    const CONTENT: &str = r#"1970-01-01 open Assets:Wallet USD,EUR
+1970-01-02 * "Exchange"
+  Assets:Bank -10 USD
+  Assets:Bank 9 EUR
+  Assets:Wallet 10 USD
+  Assets:Wallet -9 EUR
+  Assets:Cash 1 USD
+1970-01-01 * "Deposit"
+  Assets:Cash 1 CNY
+  Equity:Opening
+"#;
+
+    #[test]
+    fn more_than_one_commodity() {
+        let content = format!("plugin \"beancount.plugins.onecommodity\"\n{}", CONTENT);
+        let ledger = load(&content).unwrap();
+        let date = NaiveDate::from_ymd_opt(1970, 1, 2).unwrap();
+        let location = Location(Some(3));
+        assert_eq!(
+            vec![
+                BeanCountError::MultipleCommodities(date, "Assets:Bank".to_owned(), location),
+                BeanCountError::MultipleCommodities(date, "Assets:Cash".to_owned(), location),
+            ],
+            ledger.errors
+        );
+    }
+
+    #[test]
+    fn skip_accounts_matching_config() {
+        let ledger = load(&format!(
+            "plugin \"beancount.plugins.onecommodity\" \"Assets:(Bank|Cash)\"\n{}",
+            CONTENT
+        ))
+        .unwrap();
+        assert!(ledger.errors.is_empty());
+
+        let ledger = load("plugin \"beancount.plugins.onecommodity\" \"(\"\n").unwrap();
+        assert_eq!(
+            vec![BeanCountError::InvalidPluginConfig(
+                "beancount.plugins.onecommodity".to_owned(),
+                "(".to_owned()
+            )],
+            ledger.errors
+        );
+    }
+}
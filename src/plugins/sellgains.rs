@@ -0,0 +1,140 @@
+use crate::{
+    booking::book, error::BeanCountError, models::Directive, options::Options, plugins::Plugin,
+    to_file::ToBeancountFile,
+};
+use bigdecimal::{BigDecimal, Signed};
+use indexmap::IndexMap;
+use std::str::FromStr;
+
+/// `beancount.plugins.sellgains`, check income of transactions selling lots at cost
+///
+/// For postings reducing a lot held at cost with a `@` price, proceeds minus cost basis must
+/// match the postings to Income accounts. The tolerance of a currency is the number given as
+/// config, or `inferred_tolerance_default` of options.
+pub struct SellGains;
+
+impl Plugin for SellGains {
+    fn process(
+        &self,
+        entries: Vec<Directive>,
+        options: &Options,
+        config: Option<&str>,
+    ) -> (Vec<Directive>, Vec<BeanCountError>) {
+        let tolerance = match config.map(BigDecimal::from_str).transpose() {
+            Ok(tolerance) => tolerance,
+            Err(_) => {
+                let error = BeanCountError::InvalidPluginConfig(
+                    "beancount.plugins.sellgains".to_owned(),
+                    config.unwrap_or_default().to_owned(),
+                );
+                return (entries, vec![error]);
+            }
+        };
+        let mut errors = vec![];
+        for directive in &entries {
+            let transaction = match directive {
+                Directive::Transaction(transaction) => transaction,
+                _ => continue,
+            };
+            // transactions which cannot be booked are left to the booking errors of the ledger
+            let postings = match book(transaction) {
+                Ok(postings) => postings,
+                Err(_) => continue,
+            };
+            // currency -> gains expected from sales plus weights of income postings
+            let mut residual: IndexMap<String, BigDecimal> = IndexMap::new();
+            let mut sold = false;
+            for posting in &postings {
+                match (&posting.cost, &posting.price) {
+                    (Some(cost), Some(price))
+                        if posting.units.0.is_negative() && cost.1 == price.1 =>
+                    {
+                        sold = true;
+                        let gain = -&posting.units.0 * (&price.0 - &cost.0);
+                        *residual.entry(price.1.clone()).or_default() += gain;
+                    }
+                    _ if posting.account.is_income() => {
+                        *residual.entry(posting.weight.1.clone()).or_default() += &posting.weight.0;
+                    }
+                    _ => {}
+                }
+            }
+            if !sold {
+                continue;
+            }
+            for (currency, number) in residual {
+                let allowed = tolerance
+                    .clone()
+                    .unwrap_or_else(|| options.default_tolerance(&currency));
+                if number.abs() > allowed {
+                    errors.push(BeanCountError::InvalidGains(
+                        transaction.date,
+                        (number, currency).to_text(),
+                    ));
+                }
+            }
+        }
+        (entries, errors)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{error::BeanCountError, loader::load};
+    use chrono::NaiveDate;
+
+    const CONTENT: &str = r#"2020-01-01 * "Buy"
+  Assets:Broker 10 HOOL {100 USD}
+  Assets:Bank
+2020-02-01 * "Sell"
+  Assets:Broker -2 HOOL {100 USD} @ 120 USD
+  Assets:Bank 240 USD
+  Income:Gains -40 USD
+2020-03-01 * "Sell"
+  Assets:Broker -2 HOOL {100 USD} @ 120 USD
+  Assets:Bank 240.004 USD
+  Income:Gains -40.004 USD
+2020-04-01 * "Sell"
+  Assets:Broker -2 HOOL {100 USD} @ 130 USD
+  Assets:Bank 250 USD
+  Income:Gains -50 USD
+"#;
+
+    #[test]
+    fn gains_within_tolerance() {
+        let content = format!(
+            "plugin \"beancount.plugins.sellgains\"\noption \"inferred_tolerance_default\" \"USD:0.005\"\n{}",
+            CONTENT
+        );
+        let ledger = load(&content).unwrap();
+        assert_eq!(
+            vec![BeanCountError::InvalidGains(
+                NaiveDate::from_ymd_opt(2020, 4, 1).unwrap(),
+                "10 USD".to_owned()
+            )],
+            ledger.errors
+        );
+
+        let content = format!(
+            "plugin \"beancount.plugins.sellgains\" \"0.001\"\n{}",
+            CONTENT
+        );
+        assert_eq!(2, load(&content).unwrap().errors.len());
+    }
+
+    #[test]
+    fn skip_unbookable_transaction() {
+        let content = format!(
+            "plugin \"beancount.plugins.sellgains\" \"0.005\"\n{}2020-05-01 * \"Sell\"\n  Assets:Broker -2 HOOL {{100 USD}} @ 130 USD\n  Assets:Bank\n  Income:Gains\n",
+            CONTENT
+        );
+        let ledger = load(&content).unwrap();
+        assert_eq!(
+            vec![BeanCountError::InvalidGains(
+                NaiveDate::from_ymd_opt(2020, 4, 1).unwrap(),
+                "10 USD".to_owned()
+            )],
+            ledger.errors
+        );
+    }
+}
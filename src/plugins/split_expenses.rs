@@ -0,0 +1,173 @@
+use crate::{
+    booking::book,
+    error::BeanCountError,
+    models::{Account, Amount, Directive, Transaction, TransactionLine},
+    options::Options,
+    plugins::Plugin,
+};
+use bigdecimal::BigDecimal;
+
+/// tag of transactions whose expenses are split among members
+pub const SPLIT_TAG: &str = "split-expenses";
+
+/// `beancount.plugins.split_expenses`, split expenses evenly among members given as config
+///
+/// Each posting to an `Expenses` account is replaced by one posting per member to the
+/// sub-account named after the member, the last member takes the rounding residual.
+/// Transactions are tagged with `SPLIT_TAG`, the original account is the parent of the new
+/// postings and its amount is their sum. Postings to a member account are kept.
+/// ```rust
+/// use beancount::{loader::load, to_file::ToBeancountFile};
+/// let ledger = load(
+///     "plugin \"beancount.plugins.split_expenses\" \"Alice Bob\"\n1970-01-01 * \"Taxi\"\n  Assets:Cash -10 USD\n  Expenses:Taxi\n",
+/// ).unwrap();
+/// assert_eq!(
+///     "1970-01-01 * \"Taxi\" #split-expenses\n  Assets:Cash -10 USD\n  Expenses:Taxi:Alice 5.00 USD\n  Expenses:Taxi:Bob 5.00 USD",
+///     ledger.directives[1].to_text()
+/// );
+/// ```
+pub struct SplitExpenses;
+
+impl Plugin for SplitExpenses {
+    fn process(
+        &self,
+        entries: Vec<Directive>,
+        _options: &Options,
+        config: Option<&str>,
+    ) -> (Vec<Directive>, Vec<BeanCountError>) {
+        let members: Vec<&str> = config
+            .unwrap_or_default()
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|member| !member.is_empty())
+            .collect();
+        if members.is_empty() {
+            let error = BeanCountError::InvalidPluginConfig(
+                "beancount.plugins.split_expenses".to_owned(),
+                config.unwrap_or_default().to_owned(),
+            );
+            return (entries, vec![error]);
+        }
+
+        let mut errors = vec![];
+        let entries = entries
+            .into_iter()
+            .map(|directive| match directive {
+                Directive::Transaction(transaction) => {
+                    match split_transaction(transaction.clone(), &members) {
+                        Ok(split) => Directive::Transaction(split),
+                        Err(error) => {
+                            errors.push(error);
+                            Directive::Transaction(transaction)
+                        }
+                    }
+                }
+                directive => directive,
+            })
+            .collect();
+        (entries, errors)
+    }
+}
+
+fn split_transaction(
+    mut transaction: Transaction,
+    members: &[&str],
+) -> Result<Transaction, BeanCountError> {
+    let is_shared = |account: &Account| {
+        account.is_expense()
+            && account
+                .components()
+                .last()
+                .is_none_or(|leaf| !members.contains(&leaf.as_str()))
+    };
+    if !transaction
+        .lines
+        .iter()
+        .any(|line| is_shared(&line.account))
+    {
+        return Ok(transaction);
+    }
+
+    // booked units of each line, a line without amount gets one per residual currency
+    let mut postings = book(&transaction)?.into_iter();
+    let filled = postings.len() + 1 - transaction.lines.len();
+    let mut lines = vec![];
+    for line in std::mem::take(&mut transaction.lines) {
+        let count = if line.amount.is_some() { 1 } else { filled };
+        let units: Vec<Amount> = postings
+            .by_ref()
+            .take(count)
+            .map(|posting| posting.units)
+            .collect();
+        if !is_shared(&line.account) || line.total_price.is_some() {
+            lines.push(line);
+            continue;
+        }
+        for amount in units {
+            for (member, share) in members.iter().zip(shares(&amount.0, members.len())) {
+                let mut components = line.account.components().to_vec();
+                components.push((*member).to_owned());
+                lines.push(TransactionLine {
+                    account: line.account.with_components(components),
+                    amount: Some((share, amount.1.clone())),
+                    comment: None,
+                    ..line.clone()
+                });
+            }
+        }
+    }
+    transaction.lines = lines;
+    if !transaction.tags.iter().any(|tag| tag == SPLIT_TAG) {
+        transaction.tags.push(SPLIT_TAG.to_owned());
+    }
+    Ok(transaction)
+}
+
+/// divide number into even shares rounded to at least two decimals, the last one takes the rest
+fn shares(number: &BigDecimal, count: usize) -> Vec<BigDecimal> {
+    let scale = number.as_bigint_and_exponent().1.max(2);
+    let share = (number / BigDecimal::from(count as u64)).with_scale(scale);
+    let mut shares = vec![share.clone(); count - 1];
+    let rest = number - &share * BigDecimal::from((count - 1) as u64);
+    shares.push(rest);
+    shares
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{loader::load, models::Directive, to_file::ToBeancountFile};
+
+    #[test]
+    fn split_among_members() {
+        let content = r#"plugin "beancount.plugins.split_expenses" "Alice,Bob Carol"
+2020-01-01 * "Hotel"
+  Assets:Cash -100 USD
+  Expenses:Travel:Hotel 90 USD
+  Expenses:Travel:Tips
+2020-01-02 * "Souvenir"
+  Assets:Cash -5 USD
+  Expenses:Gifts:Alice
+"#;
+        let ledger = load(content).unwrap();
+        assert!(ledger.errors.is_empty());
+        let texts: Vec<String> = ledger
+            .directives
+            .iter()
+            .filter(|directive| matches!(directive, Directive::Transaction(_)))
+            .map(|directive| directive.to_text())
+            .collect();
+        assert_eq!(
+            vec![
+                "2020-01-01 * \"Hotel\" #split-expenses\n  \
+                 Assets:Cash -100 USD\n  \
+                 Expenses:Travel:Hotel:Alice 30.00 USD\n  \
+                 Expenses:Travel:Hotel:Bob 30.00 USD\n  \
+                 Expenses:Travel:Hotel:Carol 30.00 USD\n  \
+                 Expenses:Travel:Tips:Alice 3.33 USD\n  \
+                 Expenses:Travel:Tips:Bob 3.33 USD\n  \
+                 Expenses:Travel:Tips:Carol 3.34 USD",
+                "2020-01-02 * \"Souvenir\"\n  Assets:Cash -5 USD\n  Expenses:Gifts:Alice",
+            ],
+            texts
+        );
+    }
+}
@@ -0,0 +1,227 @@
+use crate::{
+    booking::book,
+    error::BeanCountError,
+    inventory::Inventory,
+    models::{Account, AccountType, Directive, Flag, Transaction, TransactionLine},
+    options::Options,
+    plugins::Plugin,
+    prices::PriceMap,
+};
+use bigdecimal::{BigDecimal, Zero};
+use indexmap::{IndexMap, IndexSet};
+use std::collections::{BTreeMap, HashSet};
+
+/// `beancount.plugins.unrealized`, book unrealized gains of positions held at cost
+///
+/// At the date of the last entry, the market value of each commodity held at cost in an account
+/// is compared with its book cost. The difference is booked by a transaction flagged `U` from
+/// `Income:<config>` to `<account>:<config>`, config defaults to `Unrealized`. Commodities
+/// without price are skipped. Accounts used by these transactions which are not opened yet are
+/// opened at the date of the first entry.
+pub struct Unrealized;
+
+impl Plugin for Unrealized {
+    fn process(
+        &self,
+        entries: Vec<Directive>,
+        options: &Options,
+        config: Option<&str>,
+    ) -> (Vec<Directive>, Vec<BeanCountError>) {
+        let sub_account: Vec<String> = config
+            .unwrap_or("Unrealized")
+            .split(':')
+            .map(|component| component.to_owned())
+            .collect();
+        let dates = entries.iter().filter_map(|directive| directive.date());
+        let (start, end) = match (dates.clone().min(), dates.max()) {
+            (Some(start), Some(end)) => (*start, *end),
+            _ => return (entries, vec![]),
+        };
+        let prices = PriceMap::from_directives(&entries);
+
+        let mut balances: BTreeMap<Account, Inventory> = BTreeMap::new();
+        for directive in &entries {
+            if let Directive::Transaction(transaction) = directive {
+                // ambiguous transactions add nothing, they are reported when the ledger is booked
+                for posting in book(transaction).unwrap_or_default() {
+                    balances
+                        .entry(posting.account.clone())
+                        .or_default()
+                        .add(posting.position());
+                }
+            }
+        }
+
+        let income = options
+            .account_names
+            .account(AccountType::Income, sub_account.clone());
+        let mut accounts: IndexSet<Account> = IndexSet::new();
+        let mut gains = vec![];
+        for (account, inventory) in balances {
+            // (commodity, cost currency) -> (units, book cost)
+            let mut holdings: IndexMap<(String, String), (BigDecimal, BigDecimal)> =
+                IndexMap::new();
+            for position in inventory.positions() {
+                if let Some(cost) = &position.cost {
+                    let book_cost = position.at_cost();
+                    let holding = holdings
+                        .entry((position.units.1.clone(), cost.1.clone()))
+                        .or_default();
+                    holding.0 += &position.units.0;
+                    holding.1 += book_cost.0;
+                }
+            }
+            for ((commodity, currency), (units, book_cost)) in holdings {
+                let price = match prices.get(&commodity, &currency, &end) {
+                    Some(price) => price,
+                    None => continue,
+                };
+                let gain = &units * &price - book_cost;
+                if gain.is_zero() {
+                    continue;
+                }
+                let holding_account =
+                    account.with_components([account.components(), &sub_account].concat());
+                accounts.insert(holding_account.clone());
+                accounts.insert(income.clone());
+                let narration = format!(
+                    "Unrealized gain for {} {} at {} {}",
+                    units, commodity, price, currency
+                );
+                let lines = vec![
+                    unrealized_line(holding_account, (gain.clone(), currency.clone())),
+                    unrealized_line(income.clone(), (-gain, currency)),
+                ];
+                gains.push(Directive::Transaction(Transaction::new(
+                    end,
+                    Flag::Unrealized,
+                    None,
+                    Some(narration),
+                    vec![],
+                    vec![],
+                    lines,
+                )));
+            }
+        }
+
+        let opened: HashSet<&Account> = entries
+            .iter()
+            .filter_map(|directive| match directive {
+                Directive::Open { account, .. } => Some(account),
+                _ => None,
+            })
+            .collect();
+        let opens: Vec<Directive> = accounts
+            .into_iter()
+            .filter(|account| !opened.contains(account))
+            .map(|account| Directive::Open {
+                date: start,
+                account,
+                commodities: None,
+                comment: None,
+            })
+            .collect();
+
+        let mut entries = entries;
+        entries.extend(opens);
+        entries.extend(gains);
+        (entries, vec![])
+    }
+}
+
+fn unrealized_line(account: Account, amount: (BigDecimal, String)) -> TransactionLine {
+    TransactionLine {
+        flag: Flag::Complete,
+        account,
+        amount: Some(amount),
+        cost: None,
+        single_price: None,
+        total_price: None,
+        comment: None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{loader::load, models::Directive, to_file::ToBeancountFile};
+
+    #[test]
+    fn book_gains_at_last_price() {
+        let content = r#"plugin "beancount.plugins.unrealized" "Gains:Unrealized"
+2020-01-01 * "Buy"
+  Assets:Broker 2 HOOL {100 USD}
+  Assets:Broker 1 HOOL {110 USD}
+  Assets:Bank
+2020-01-02 * "Buy"
+  Assets:Broker 5 GOOG {10 USD}
+  Assets:Bank
+2020-01-03 * "Buy"
+  Assets:Retirement 1 HOOL {130 USD}
+  Assets:Bank
+2020-01-31 price HOOL 120 USD
+"#;
+        let ledger = load(content).unwrap();
+        assert!(ledger.errors.is_empty());
+        let gains: Vec<String> = ledger
+            .directives
+            .iter()
+            .filter(|directive| match directive {
+                Directive::Transaction(transaction) => transaction.flag.to_text() == "U",
+                _ => false,
+            })
+            .map(|directive| directive.to_text())
+            .collect();
+        assert_eq!(
+            vec![
+                "2020-01-31 U \"Unrealized gain for 3 HOOL at 120 USD\"\n  Assets:Broker:Gains:Unrealized 50 USD\n  Income:Gains:Unrealized -50 USD",
+                "2020-01-31 U \"Unrealized gain for 1 HOOL at 120 USD\"\n  Assets:Retirement:Gains:Unrealized -10 USD\n  Income:Gains:Unrealized 10 USD",
+            ],
+            gains
+        );
+        let opens: Vec<String> = ledger
+            .directives
+            .iter()
+            .filter(|directive| matches!(directive, Directive::Open { .. }))
+            .map(|directive| directive.to_text())
+            .collect();
+        assert_eq!(
+            vec![
+                "2020-01-01 open Assets:Broker:Gains:Unrealized",
+                "2020-01-01 open Income:Gains:Unrealized",
+                "2020-01-01 open Assets:Retirement:Gains:Unrealized",
+            ],
+            opens
+        );
+    }
+
+    #[test]
+    fn custom_income_root() {
+        let content = r#"option "name_income" "Revenue"
+plugin "beancount.plugins.unrealized"
+2020-01-01 open Assets:Broker
+2020-01-01 open Assets:Bank
+2020-01-01 open Revenue:Unrealized
+2020-01-01 * "Buy"
+  Assets:Broker 2 HOOL {100 USD}
+  Assets:Bank
+2020-01-02 * "Ambiguous"
+  Assets:Broker 1 HOOL {100 USD}
+  Assets:Bank
+  Revenue:Unrealized
+2020-01-31 price HOOL 120 USD
+"#;
+        let ledger = load(content).unwrap();
+        assert!(ledger.errors.is_empty());
+        let texts: Vec<String> = ledger.directives[ledger.directives.len() - 2..]
+            .iter()
+            .map(|directive| directive.to_text())
+            .collect();
+        assert_eq!(
+            vec![
+                "2020-01-01 open Assets:Broker:Unrealized",
+                "2020-01-31 U \"Unrealized gain for 2 HOOL at 120 USD\"\n  Assets:Broker:Unrealized 40 USD\n  Revenue:Unrealized -40 USD",
+            ],
+            texts
+        );
+    }
+}
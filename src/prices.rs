@@ -0,0 +1,75 @@
+use crate::models::{Amount, Directive};
+use bigdecimal::{BigDecimal, Zero};
+use chrono::NaiveDate;
+use std::collections::{BTreeMap, HashMap};
+
+/// price history of commodity pairs built from `price` directives
+#[derive(Debug, Default, Clone)]
+pub struct PriceMap {
+    prices: HashMap<(String, String), BTreeMap<NaiveDate, BigDecimal>>,
+}
+
+impl PriceMap {
+    pub fn new() -> Self {
+        PriceMap::default()
+    }
+
+    pub fn from_directives(directives: &[Directive]) -> Self {
+        let mut map = PriceMap::new();
+        for directive in directives {
+            if let Directive::Price {
+                date,
+                commodity,
+                amount,
+                ..
+            } = directive
+            {
+                map.insert(*date, commodity, amount.clone());
+            }
+        }
+        map
+    }
+
+    /// record price of one unit of commodity, later one wins in the same day
+    pub fn insert(&mut self, date: NaiveDate, commodity: &str, price: Amount) {
+        self.prices
+            .entry((commodity.to_owned(), price.1))
+            .or_default()
+            .insert(date, price.0);
+    }
+
+    /// latest price of `base` in `quote` on or before date, inverted price is used as fallback
+    /// ```rust
+    /// use beancount::prices::PriceMap;
+    /// use bigdecimal::BigDecimal;
+    /// use chrono::NaiveDate;
+    /// let mut prices = PriceMap::new();
+    /// let date = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap();
+    /// prices.insert(date, "USD", (BigDecimal::from(4), "CNY".to_owned()));
+    /// assert_eq!(Some(BigDecimal::from(4)), prices.get("USD", "CNY", &date));
+    /// assert_eq!(Some(BigDecimal::from(1) / BigDecimal::from(4)), prices.get("CNY", "USD", &date));
+    /// assert_eq!(None, prices.get("USD", "CNY", &date.pred_opt().unwrap()));
+    /// ```
+    pub fn get(&self, base: &str, quote: &str, date: &NaiveDate) -> Option<BigDecimal> {
+        if base == quote {
+            return Some(BigDecimal::from(1));
+        }
+        let latest = |base: &str, quote: &str| {
+            self.prices
+                .get(&(base.to_owned(), quote.to_owned()))
+                .and_then(|history| history.range(..=*date).next_back())
+                .map(|(_, price)| price.clone())
+        };
+        latest(base, quote).or_else(|| {
+            latest(quote, base)
+                .filter(|price| !price.is_zero())
+                .map(|price| BigDecimal::from(1) / price)
+        })
+    }
+
+    /// convert amount into currency, `None` if no price is found
+    pub fn convert(&self, amount: &Amount, currency: &str, date: &NaiveDate) -> Option<Amount> {
+        self.get(&amount.1, currency, date)
+            .map(|price| (&amount.0 * price, currency.to_owned()))
+    }
+}
@@ -0,0 +1,156 @@
+use crate::query::Value;
+use chrono::NaiveDate;
+use std::fmt::{Display, Formatter};
+
+/// a parsed `SELECT` statement
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    pub distinct: bool,
+    /// selected expressions, empty for `SELECT *`
+    pub targets: Vec<Target>,
+    pub from: FromClause,
+    pub where_clause: Option<Expr>,
+    pub group_by: Vec<Expr>,
+    pub order_by: Vec<(Expr, Order)>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+    pub expr: Expr,
+    pub alias: Option<String>,
+}
+
+impl Target {
+    /// name of result column, the alias or the text of expression
+    pub fn name(&self) -> String {
+        self.alias.clone().unwrap_or_else(|| self.expr.to_string())
+    }
+}
+
+/// the table rows are selected from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Table {
+    /// one row per posting of transactions
+    Postings,
+    /// one row per dated directive
+    Entries,
+}
+
+/// `FROM` clause, the filter applies to entries before they are opened, closed and cleared
+#[derive(Debug, Clone, PartialEq)]
+pub struct FromClause {
+    pub table: Table,
+    pub filter: Option<Expr>,
+    pub open: Option<NaiveDate>,
+    pub close: Option<NaiveDate>,
+    pub clear: bool,
+}
+
+impl Default for FromClause {
+    fn default() -> Self {
+        FromClause {
+            table: Table::Postings,
+            filter: None,
+            open: None,
+            close: None,
+            clear: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Order {
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Column(String),
+    Literal(Value),
+    Function(String, Vec<Expr>),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Not,
+    Neg,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    And,
+    Or,
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    /// regular expression search
+    Match,
+    In,
+    NotIn,
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl BinaryOp {
+    fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOp::And => "AND",
+            BinaryOp::Or => "OR",
+            BinaryOp::Eq => "=",
+            BinaryOp::NotEq => "!=",
+            BinaryOp::Lt => "<",
+            BinaryOp::LtEq => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::GtEq => ">=",
+            BinaryOp::Match => "~",
+            BinaryOp::In => "IN",
+            BinaryOp::NotIn => "NOT IN",
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+        }
+    }
+}
+
+impl Expr {
+    /// whether expression contains an aggregate function like `sum`
+    pub fn is_aggregate(&self) -> bool {
+        match self {
+            Expr::Column(_) | Expr::Literal(_) => false,
+            Expr::Function(name, args) => {
+                crate::query::functions::is_aggregate(name)
+                    || args.iter().any(|arg| arg.is_aggregate())
+            }
+            Expr::Unary(_, expr) => expr.is_aggregate(),
+            Expr::Binary(_, left, right) => left.is_aggregate() || right.is_aggregate(),
+        }
+    }
+}
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expr::Column(name) => write!(f, "{}", name),
+            Expr::Literal(Value::String(text)) => write!(f, "'{}'", text),
+            Expr::Literal(Value::Null) => write!(f, "NULL"),
+            Expr::Literal(value) => write!(f, "{}", value),
+            Expr::Function(name, args) => {
+                let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+                write!(f, "{}({})", name, args.join(", "))
+            }
+            Expr::Unary(UnaryOp::Not, expr) => write!(f, "NOT {}", expr),
+            Expr::Unary(UnaryOp::Neg, expr) => write!(f, "-{}", expr),
+            Expr::Binary(op, left, right) => write!(f, "{} {} {}", left, op.symbol(), right),
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use crate::{
+    inventory::Position,
+    models::Amount,
+    query::{DataType, QueryResult, Value},
+    reports::Table,
+};
+use strum_macros::EnumString;
+
+/// output formats of query result, named like the `--format` choices of bean-query
+#[derive(Debug, EnumString, PartialEq, Clone, Copy, strum_macros::Display)]
+pub enum Format {
+    #[strum(serialize = "text")]
+    Text,
+    #[strum(serialize = "csv")]
+    Csv,
+    #[strum(serialize = "jsonl")]
+    JsonLines,
+}
+
+impl QueryResult {
+    pub fn render(&self, format: Format) -> String {
+        match format {
+            Format::Text => self.to_text(),
+            Format::Csv => self.to_csv(),
+            Format::JsonLines => self.to_json_lines(),
+        }
+    }
+
+    /// render as aligned table, numbers are aligned at decimal point and amounts to the right
+    /// ```rust
+    /// use beancount::{loader::load, query::execute};
+    /// let ledger = load("1970-01-01 * \"Lunch\"\n  Assets:Cash -10.5 USD\n  Expenses:Food\n").unwrap();
+    /// let result = execute("SELECT account, number", &ledger.directives, &ledger.options).unwrap();
+    /// assert_eq!(
+    ///     "account        number\n-------------  ------\nAssets:Cash     -10.5\nExpenses:Food    10.5\n",
+    ///     result.to_text()
+    /// );
+    /// ```
+    pub fn to_text(&self) -> String {
+        let mut columns: Vec<Vec<String>> = vec![];
+        for (index, column) in self.columns.iter().enumerate() {
+            let values = self.rows.iter().map(|row| &row[index]);
+            let mut cells = vec![column.name.clone()];
+            match column.data_type {
+                DataType::Number => cells.extend(decimal_aligned(values)),
+                _ => cells.extend(values.map(|value| value.to_string())),
+            }
+            let width = cells
+                .iter()
+                .map(|cell| cell.chars().count())
+                .max()
+                .unwrap_or(0);
+            let right = matches!(
+                column.data_type,
+                DataType::Number | DataType::Amount | DataType::Position | DataType::Inventory
+            );
+            let mut aligned: Vec<String> = cells
+                .iter()
+                .map(|cell| {
+                    if right {
+                        format!("{:>width$}", cell, width = width)
+                    } else {
+                        format!("{:<width$}", cell, width = width)
+                    }
+                })
+                .collect();
+            aligned.insert(1, "-".repeat(width));
+            columns.push(aligned);
+        }
+
+        let mut text = String::new();
+        for line in 0..self.rows.len() + 2 {
+            let cells: Vec<&str> = columns.iter().map(|cells| cells[line].as_str()).collect();
+            text.push_str(cells.join("  ").trim_end());
+            text.push('\n');
+        }
+        text
+    }
+
+    /// render as CSV with a header row
+    pub fn to_csv(&self) -> String {
+        Table {
+            headers: self
+                .columns
+                .iter()
+                .map(|column| column.name.clone())
+                .collect(),
+            rows: self
+                .rows
+                .iter()
+                .map(|row| row.iter().map(|value| value.to_string()).collect())
+                .collect(),
+            text_columns: self.columns.len(),
+        }
+        .to_csv()
+    }
+
+    /// render one JSON object per row, numbers are kept as strings to keep their precision
+    ///
+    /// Amounts are objects with `number` and `currency`, positions add their `cost` amount and
+    /// inventories are lists of positions.
+    /// ```rust
+    /// use beancount::{loader::load, query::execute};
+    /// let ledger = load("1970-01-01 * \"Lunch\" #food\n  Assets:Cash -10 USD\n  Expenses:Food\n").unwrap();
+    /// let result = execute("SELECT date, tags, weight LIMIT 1", &ledger.directives, &ledger.options).unwrap();
+    /// assert_eq!(
+    ///     "{\"date\":\"1970-01-01\",\"tags\":[\"food\"],\"weight\":{\"number\":\"-10\",\"currency\":\"USD\"}}\n",
+    ///     result.to_json_lines()
+    /// );
+    /// ```
+    pub fn to_json_lines(&self) -> String {
+        self.rows
+            .iter()
+            .map(|row| {
+                let fields: Vec<String> = self
+                    .columns
+                    .iter()
+                    .zip(row.iter())
+                    .map(|(column, value)| {
+                        format!(
+                            "{}:{}",
+                            serde_json::Value::String(column.name.clone()),
+                            json(value)
+                        )
+                    })
+                    .collect();
+                format!("{{{}}}\n", fields.join(","))
+            })
+            .collect()
+    }
+}
+
+/// JSON text of value, independent of the serde shape of internal types
+fn json(value: &Value) -> String {
+    let string = |text: String| serde_json::Value::String(text).to_string();
+    let amount = |(number, currency): &Amount| {
+        format!(
+            "{{\"number\":{},\"currency\":{}}}",
+            string(number.to_string()),
+            string(currency.clone())
+        )
+    };
+    let position = |position: &Position| {
+        format!(
+            "{{\"units\":{},\"cost\":{}}}",
+            amount(&position.units),
+            position
+                .cost
+                .as_ref()
+                .map(amount)
+                .unwrap_or_else(|| "null".to_owned())
+        )
+    };
+    match value {
+        Value::Null => "null".to_owned(),
+        Value::Boolean(boolean) => boolean.to_string(),
+        Value::Amount(value) => amount(value),
+        Value::Position(value) => position(value),
+        Value::Inventory(inventory) => {
+            let positions: Vec<String> = inventory.positions().iter().map(position).collect();
+            format!("[{}]", positions.join(","))
+        }
+        Value::Set(set) => {
+            let items: Vec<String> = set.iter().cloned().map(string).collect();
+            format!("[{}]", items.join(","))
+        }
+        Value::Number(_) | Value::String(_) | Value::Date(_) => string(value.to_string()),
+    }
+}
+
+/// render numbers padded so that their decimal points line up
+fn decimal_aligned<'a>(values: impl Iterator<Item = &'a Value>) -> Vec<String> {
+    let texts: Vec<String> = values.map(|value| value.to_string()).collect();
+    let fraction = |text: &str| text.find('.').map(|index| text.len() - index).unwrap_or(0);
+    let width = texts.iter().map(|text| fraction(text)).max().unwrap_or(0);
+    texts
+        .iter()
+        .map(|text| {
+            if text.is_empty() {
+                String::new()
+            } else {
+                format!("{}{}", text, " ".repeat(width - fraction(text)))
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        loader::load,
+        query::{execute, format::Format},
+    };
+    use std::str::FromStr;
+
+    const LEDGER: &str = r#"2020-01-01 open Assets:Bank
+2020-01-01 open Assets:Broker
+2020-01-01 open Expenses:Food
+2020-01-05 * "Shop, Inc" "Lunch"
+  Assets:Bank -20.25 USD
+  Expenses:Food
+2020-01-06 * "Shop, Inc" "Coffee"
+  Assets:Bank -3 USD
+  Expenses:Food
+2020-02-15 * "Broker" "Buy"
+  Assets:Broker 2 HOOL {100 USD}
+  Assets:Bank
+"#;
+
+    #[test]
+    fn render_formats() {
+        let ledger = load(LEDGER).unwrap();
+        let result = execute(
+            "SELECT payee, sum(number) AS total, sum(position) AS balance \
+             WHERE account ~ 'Expenses|Broker' GROUP BY payee ORDER BY payee",
+            &ledger.directives,
+            &ledger.options,
+        )
+        .unwrap();
+
+        assert_eq!(
+            "payee      total           balance\n\
+             ---------  -----  ----------------\n\
+             Broker      2     2 HOOL {100 USD}\n\
+             Shop, Inc  23.25         23.25 USD\n",
+            result.render(Format::from_str("text").unwrap())
+        );
+        assert_eq!(
+            "payee,total,balance\n\
+             Broker,2,2 HOOL {100 USD}\n\
+             \"Shop, Inc\",23.25,23.25 USD\n",
+            result.render(Format::Csv)
+        );
+        assert_eq!(
+            "{\"payee\":\"Broker\",\"total\":\"2\",\"balance\":[{\"units\":{\"number\":\"2\",\"currency\":\"HOOL\"},\"cost\":{\"number\":\"100\",\"currency\":\"USD\"}}]}\n\
+             {\"payee\":\"Shop, Inc\",\"total\":\"23.25\",\"balance\":[{\"units\":{\"number\":\"23.25\",\"currency\":\"USD\"},\"cost\":null}]}\n",
+            result.render(Format::JsonLines)
+        );
+    }
+}
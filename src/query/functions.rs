@@ -0,0 +1,186 @@
+use crate::{
+    error::BeanCountError,
+    inventory::Inventory,
+    models::{AccountNames, AccountType},
+    prices::PriceMap,
+    query::{DataType, Value},
+};
+use bigdecimal::BigDecimal;
+use chrono::{Datelike, NaiveDate};
+use std::cmp::Ordering;
+
+pub(crate) fn is_aggregate(name: &str) -> bool {
+    matches!(name, "sum" | "count" | "first" | "last" | "min" | "max")
+}
+
+/// type of the result of function, error if function does not accept the arguments
+pub(crate) fn data_type(name: &str, args: &[DataType]) -> Result<DataType, BeanCountError> {
+    use DataType::*;
+    let data_type = match (name, args) {
+        ("units", [Amount]) | ("units", [Position]) => Some(Amount),
+        ("cost", [Amount]) | ("cost", [Position]) => Some(Amount),
+        ("value", [Position]) | ("value", [Position, Date]) => Some(Amount),
+        ("units", [Inventory]) | ("cost", [Inventory]) | ("value", [Inventory]) => Some(Inventory),
+        ("value", [Inventory, Date]) => Some(Inventory),
+        ("year", [Date]) | ("month", [Date]) | ("day", [Date]) => Some(Number),
+        ("account_sortkey", [String]) | ("parent", [String]) | ("leaf", [String]) => Some(String),
+        ("root", [String, Number]) => Some(String),
+        ("number", [Amount]) | ("number", [Position]) => Some(Number),
+        ("currency", [Amount]) | ("currency", [Position]) => Some(String),
+        ("length", [String]) | ("length", [Set]) => Some(Number),
+        ("abs", [Number]) => Some(Number),
+        ("abs", [Amount]) => Some(Amount),
+        ("str", [_]) => Some(String),
+        ("sum", [Number]) => Some(Number),
+        ("sum", [Amount]) | ("sum", [Position]) | ("sum", [Inventory]) => Some(Inventory),
+        ("count", [_]) => Some(Number),
+        ("first", [t]) | ("last", [t]) | ("min", [t]) | ("max", [t]) => Some(*t),
+        _ => None,
+    };
+    data_type.ok_or_else(|| {
+        let args: Vec<std::string::String> = args.iter().map(|arg| arg.to_string()).collect();
+        BeanCountError::InvalidQuery(format!("no function {}({})", name, args.join(", ")))
+    })
+}
+
+/// call scalar function, arguments have been checked by `data_type`
+pub(crate) fn call(name: &str, args: &[Value], prices: &PriceMap, names: &AccountNames) -> Value {
+    match (name, args) {
+        ("units", [Value::Position(position)]) => Value::Amount(position.units.clone()),
+        ("units", [Value::Inventory(inventory)]) => Value::Inventory(inventory.units()),
+        ("cost", [Value::Position(position)]) => Value::Amount(position.at_cost()),
+        ("cost", [Value::Inventory(inventory)]) => Value::Inventory(inventory.at_cost()),
+        ("units", [Value::Amount(amount)]) | ("cost", [Value::Amount(amount)]) => {
+            Value::Amount(amount.clone())
+        }
+        ("value", [value]) => call(
+            "value",
+            &[value.clone(), Value::Date(NaiveDate::MAX)],
+            prices,
+            names,
+        ),
+        ("value", [Value::Position(position), Value::Date(date)]) => {
+            Value::Amount(position.at_value(prices, date))
+        }
+        ("value", [Value::Inventory(inventory), Value::Date(date)]) => {
+            Value::Inventory(inventory.at_value(prices, date))
+        }
+        ("year", [Value::Date(date)]) => Value::Number(BigDecimal::from(date.year())),
+        ("month", [Value::Date(date)]) => Value::Number(BigDecimal::from(date.month())),
+        ("day", [Value::Date(date)]) => Value::Number(BigDecimal::from(date.day())),
+        ("account_sortkey", [Value::String(name)]) => match names.parse_account(name) {
+            Ok(account) => {
+                let index = match account.account_type() {
+                    AccountType::Assets => 0,
+                    AccountType::Liabilities => 1,
+                    AccountType::Equity => 2,
+                    AccountType::Income => 3,
+                    AccountType::Expenses => 4,
+                };
+                Value::String(format!("{}-{}", index, name))
+            }
+            Err(_) => Value::Null,
+        },
+        ("parent", [Value::String(name)]) => match name.rfind(':') {
+            Some(index) => Value::String(name[..index].to_owned()),
+            None => Value::Null,
+        },
+        ("leaf", [Value::String(name)]) => {
+            Value::String(name.rsplit(':').next().unwrap_or_default().to_owned())
+        }
+        ("root", [Value::String(name), Value::Number(depth)]) => {
+            let depth = depth.to_string().parse::<usize>().unwrap_or(0);
+            let components: Vec<&str> = name.split(':').take(depth).collect();
+            Value::String(components.join(":"))
+        }
+        ("number", [Value::Amount(amount)]) => Value::Number(amount.0.clone()),
+        ("number", [Value::Position(position)]) => Value::Number(position.units.0.clone()),
+        ("currency", [Value::Amount(amount)]) => Value::String(amount.1.clone()),
+        ("currency", [Value::Position(position)]) => Value::String(position.units.1.clone()),
+        ("length", [Value::String(text)]) => {
+            Value::Number(BigDecimal::from(text.chars().count() as u64))
+        }
+        ("length", [Value::Set(set)]) => Value::Number(BigDecimal::from(set.len() as u64)),
+        ("abs", [Value::Number(number)]) => Value::Number(number.abs()),
+        ("abs", [Value::Amount((number, currency))]) => {
+            Value::Amount((number.abs(), currency.clone()))
+        }
+        ("str", [value]) => Value::String(value.to_string()),
+        _ => Value::Null,
+    }
+}
+
+/// running state of an aggregate function within a group
+#[derive(Debug, Clone)]
+pub(crate) enum Accumulator {
+    Sum(Value),
+    Count(u64),
+    First(Value),
+    Last(Value),
+    Min(Value),
+    Max(Value),
+}
+
+impl Accumulator {
+    pub(crate) fn new(name: &str, data_type: DataType) -> Self {
+        match name {
+            "sum" if data_type == DataType::Number => {
+                Accumulator::Sum(Value::Number(BigDecimal::from(0)))
+            }
+            "sum" => Accumulator::Sum(Value::Inventory(Inventory::new())),
+            "count" => Accumulator::Count(0),
+            "first" => Accumulator::First(Value::Null),
+            "last" => Accumulator::Last(Value::Null),
+            "min" => Accumulator::Min(Value::Null),
+            _ => Accumulator::Max(Value::Null),
+        }
+    }
+
+    pub(crate) fn update(&mut self, value: Value) {
+        if value == Value::Null {
+            return;
+        }
+        match self {
+            Accumulator::Sum(Value::Number(total)) => {
+                if let Value::Number(number) = value {
+                    *total += number;
+                }
+            }
+            Accumulator::Sum(Value::Inventory(total)) => match value {
+                Value::Amount(amount) => total.add_amount(amount),
+                Value::Position(position) => total.add(position),
+                Value::Inventory(inventory) => total.add_inventory(&inventory),
+                _ => {}
+            },
+            Accumulator::Sum(_) => {}
+            Accumulator::Count(count) => *count += 1,
+            Accumulator::First(first) => {
+                if *first == Value::Null {
+                    *first = value;
+                }
+            }
+            Accumulator::Last(last) => *last = value,
+            Accumulator::Min(min) => {
+                if *min == Value::Null || value.compare(min) == Ordering::Less {
+                    *min = value;
+                }
+            }
+            Accumulator::Max(max) => {
+                if *max == Value::Null || value.compare(max) == Ordering::Greater {
+                    *max = value;
+                }
+            }
+        }
+    }
+
+    pub(crate) fn value(&self) -> Value {
+        match self {
+            Accumulator::Count(count) => Value::Number(BigDecimal::from(*count)),
+            Accumulator::Sum(value)
+            | Accumulator::First(value)
+            | Accumulator::Last(value)
+            | Accumulator::Min(value)
+            | Accumulator::Max(value) => value.clone(),
+        }
+    }
+}
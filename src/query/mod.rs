@@ -0,0 +1,997 @@
+use crate::{
+    booking::{book, pad, Posting},
+    error::BeanCountError,
+    inventory::{Inventory, Position},
+    models::{sort_directives, Account, AccountNames, Amount, Directive},
+    options::Options,
+    prices::PriceMap,
+    query::{
+        ast::{BinaryOp, Expr, Order, Query, Table, Target, UnaryOp},
+        functions::Accumulator,
+    },
+    summarize,
+    to_file::ToBeancountFile,
+};
+use bigdecimal::{BigDecimal, Zero};
+use chrono::{Datelike, NaiveDate};
+use indexmap::IndexMap;
+use lalrpop_util::lalrpop_mod;
+use regex::Regex;
+use serde::Serialize;
+use std::{
+    cell::RefCell,
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+pub mod ast;
+pub mod format;
+mod functions;
+
+lalrpop_mod!(
+    #[allow(clippy::all)]
+    grammar,
+    "/query/grammar.rs"
+);
+
+/// a typed value of query
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+#[serde(untagged)]
+pub enum Value {
+    Null,
+    Boolean(bool),
+    Number(BigDecimal),
+    String(String),
+    Date(NaiveDate),
+    Amount(Amount),
+    Position(Position),
+    Inventory(Inventory),
+    /// tags or links
+    Set(Vec<String>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum_macros::Display)]
+pub enum DataType {
+    Null,
+    Boolean,
+    Number,
+    String,
+    Date,
+    Amount,
+    Position,
+    Inventory,
+    Set,
+}
+
+impl Value {
+    pub fn data_type(&self) -> DataType {
+        match self {
+            Value::Null => DataType::Null,
+            Value::Boolean(_) => DataType::Boolean,
+            Value::Number(_) => DataType::Number,
+            Value::String(_) => DataType::String,
+            Value::Date(_) => DataType::Date,
+            Value::Amount(_) => DataType::Amount,
+            Value::Position(_) => DataType::Position,
+            Value::Inventory(_) => DataType::Inventory,
+            Value::Set(_) => DataType::Set,
+        }
+    }
+
+    /// total order used by `ORDER BY`, `NULL` comes first
+    pub fn compare(&self, other: &Value) -> Ordering {
+        match (self, other) {
+            (Value::Null, Value::Null) => Ordering::Equal,
+            (Value::Null, _) => Ordering::Less,
+            (_, Value::Null) => Ordering::Greater,
+            (Value::Boolean(a), Value::Boolean(b)) => a.cmp(b),
+            (Value::Number(a), Value::Number(b)) => a.cmp(b),
+            (Value::String(a), Value::String(b)) => a.cmp(b),
+            (Value::Date(a), Value::Date(b)) => a.cmp(b),
+            (Value::Amount(a), Value::Amount(b)) => a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)),
+            (Value::Position(a), Value::Position(b)) => a
+                .units
+                .1
+                .cmp(&b.units.1)
+                .then_with(|| a.units.0.cmp(&b.units.0)),
+            (Value::Set(a), Value::Set(b)) => a.cmp(b),
+            _ => self.to_string().cmp(&other.to_string()),
+        }
+    }
+
+    fn is_true(&self) -> bool {
+        self == &Value::Boolean(true)
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Null => Ok(()),
+            Value::Boolean(true) => write!(f, "TRUE"),
+            Value::Boolean(false) => write!(f, "FALSE"),
+            Value::Number(number) => write!(f, "{}", number),
+            Value::String(text) => write!(f, "{}", text),
+            Value::Date(date) => write!(f, "{}", date),
+            Value::Amount((number, currency)) => write!(f, "{} {}", number, currency),
+            Value::Position(position) => write!(f, "{}", position.to_text()),
+            Value::Inventory(inventory) => write!(f, "{}", inventory.to_text()),
+            Value::Set(set) => write!(f, "{}", set.join(",")),
+        }
+    }
+}
+
+/// a column of query result
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    pub name: String,
+    pub data_type: DataType,
+}
+
+/// columns and typed rows returned by a query
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryResult {
+    pub columns: Vec<Column>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+impl FromStr for Query {
+    type Err = BeanCountError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        grammar::QueryParser::new()
+            .parse(s)
+            .map_err(|e| BeanCountError::InvalidQuery(e.to_string()))
+    }
+}
+
+/// parse and run query over directives
+/// ```rust
+/// use beancount::{loader::load, query::{execute, Value}};
+/// use bigdecimal::BigDecimal;
+/// let ledger = load("1970-01-01 * \"Lunch\"\n  Assets:Cash -10 USD\n  Expenses:Food\n").unwrap();
+/// let result = execute(
+///     "SELECT account, sum(number) WHERE account ~ '^Expenses' GROUP BY account",
+///     &ledger.directives,
+///     &ledger.options,
+/// )
+/// .unwrap();
+/// assert_eq!(
+///     vec![vec![
+///         Value::String("Expenses:Food".to_owned()),
+///         Value::Number(BigDecimal::from(10)),
+///     ]],
+///     result.rows
+/// );
+/// ```
+pub fn execute(
+    query: &str,
+    directives: &[Directive],
+    options: &Options,
+) -> Result<QueryResult, BeanCountError> {
+    Query::from_str(query)?.execute(directives, options)
+}
+
+impl Query {
+    pub fn execute(
+        &self,
+        directives: &[Directive],
+        options: &Options,
+    ) -> Result<QueryResult, BeanCountError> {
+        let mut entries = pad(directives)?;
+        let prices = PriceMap::from_directives(&entries);
+        let context = Context {
+            prices: &prices,
+            names: &options.account_names,
+            regexes: RefCell::new(HashMap::new()),
+        };
+
+        if let Some(filter) = &self.from.filter {
+            data_type(filter, Table::Entries)?;
+            let mut kept = vec![];
+            for entry in entries {
+                let row = Row::new(&entry, None, None);
+                if entry.date().is_none() || context.evaluate(filter, &row, None)?.is_true() {
+                    kept.push(entry);
+                }
+            }
+            entries = kept;
+        }
+        if let Some(date) = self.from.open {
+            entries = summarize::open(&entries, date, options)?;
+        }
+        if let Some(date) = self.from.close {
+            entries = summarize::close(&entries, date, options)?;
+        }
+        if self.from.clear {
+            let last = entries.iter().filter_map(|entry| entry.date()).max();
+            let date = match (self.from.close, last) {
+                (Some(date), _) => date,
+                (None, Some(last)) => last.succ_opt().unwrap_or(*last),
+                (None, None) => NaiveDate::MAX,
+            };
+            entries = summarize::clear(&entries, date, options)?;
+        }
+        sort_directives(&mut entries);
+
+        let table = self.from.table;
+        let targets = if self.targets.is_empty() {
+            default_targets(table)
+        } else {
+            self.targets.clone()
+        };
+        let mut columns = vec![];
+        for target in &targets {
+            columns.push(Column {
+                name: target.name(),
+                data_type: data_type(&target.expr, table)?,
+            });
+        }
+        if let Some(where_clause) = &self.where_clause {
+            if where_clause.is_aggregate() {
+                return Err(BeanCountError::InvalidQuery(
+                    "aggregate function is not allowed in WHERE".to_owned(),
+                ));
+            }
+            data_type(where_clause, table)?;
+        }
+
+        // expressions evaluated for each result row, the targets followed by hidden sort keys
+        let mut items: Vec<Expr> = targets.iter().map(|target| target.expr.clone()).collect();
+        let mut order_by = vec![];
+        for (expr, order) in &self.order_by {
+            let expr = resolve(expr, &targets);
+            data_type(&expr, table)?;
+            let index = match items.iter().position(|item| item == &expr) {
+                Some(index) => index,
+                None => {
+                    items.push(expr);
+                    items.len() - 1
+                }
+            };
+            order_by.push((index, *order));
+        }
+        let mut group_by = vec![];
+        for expr in &self.group_by {
+            let expr = resolve(expr, &targets);
+            if expr.is_aggregate() {
+                return Err(BeanCountError::InvalidQuery(format!(
+                    "aggregate function {} is not allowed in GROUP BY",
+                    expr
+                )));
+            }
+            data_type(&expr, table)?;
+            group_by.push(expr);
+        }
+
+        let mut booked = vec![];
+        for entry in &entries {
+            match (table, entry) {
+                (Table::Postings, Directive::Transaction(transaction)) => {
+                    booked.push((entry, book(transaction)?))
+                }
+                (Table::Entries, _) if entry.date().is_some() => booked.push((entry, vec![])),
+                _ => {}
+            }
+        }
+        let mut rows = vec![];
+        let mut balance = Inventory::new();
+        for (entry, postings) in &booked {
+            if table == Table::Entries {
+                let row = Row::new(entry, None, None);
+                if context.filter(&self.where_clause, &row)? {
+                    rows.push(row);
+                }
+                continue;
+            }
+            for posting in postings {
+                let mut running = balance.clone();
+                running.add(posting.position());
+                let row = Row::new(entry, Some(posting), Some(running));
+                if context.filter(&self.where_clause, &row)? {
+                    balance = row.balance.clone().unwrap_or_default();
+                    rows.push(row);
+                }
+            }
+        }
+
+        let aggregate = !group_by.is_empty() || items.iter().any(|item| item.is_aggregate());
+        let mut results = if aggregate {
+            context.aggregate(&items, &group_by, &rows, table)?
+        } else {
+            rows.iter()
+                .map(|row| {
+                    items
+                        .iter()
+                        .map(|item| context.evaluate(item, row, None))
+                        .collect::<Result<Vec<Value>, BeanCountError>>()
+                })
+                .collect::<Result<Vec<Vec<Value>>, BeanCountError>>()?
+        };
+
+        if self.distinct {
+            let mut seen = HashSet::new();
+            results.retain(|result| seen.insert(result[..targets.len()].to_vec()));
+        }
+        results.sort_by(|a, b| {
+            order_by
+                .iter()
+                .map(|(index, order)| {
+                    let ordering = a[*index].compare(&b[*index]);
+                    match order {
+                        Order::Asc => ordering,
+                        Order::Desc => ordering.reverse(),
+                    }
+                })
+                .find(|ordering| ordering != &Ordering::Equal)
+                .unwrap_or(Ordering::Equal)
+        });
+        if let Some(limit) = self.limit {
+            results.truncate(limit);
+        }
+        for result in &mut results {
+            result.truncate(targets.len());
+        }
+        Ok(QueryResult {
+            columns,
+            rows: results,
+        })
+    }
+}
+
+fn default_targets(table: Table) -> Vec<Target> {
+    let columns: &[&str] = match table {
+        Table::Postings => &["date", "flag", "payee", "narration", "account", "position"],
+        Table::Entries => &["date", "type", "flag", "payee", "narration"],
+    };
+    columns
+        .iter()
+        .map(|column| Target {
+            expr: Expr::Column((*column).to_owned()),
+            alias: None,
+        })
+        .collect()
+}
+
+/// replace alias or 1-based column index in `GROUP BY` and `ORDER BY` by the target expression
+fn resolve(expr: &Expr, targets: &[Target]) -> Expr {
+    let target = match expr {
+        Expr::Column(name) => targets
+            .iter()
+            .find(|target| target.alias.as_ref() == Some(name)),
+        Expr::Literal(Value::Number(number)) => number
+            .to_string()
+            .parse::<usize>()
+            .ok()
+            .and_then(|index| index.checked_sub(1))
+            .and_then(|index| targets.get(index)),
+        _ => None,
+    };
+    target
+        .map(|target| target.expr.clone())
+        .unwrap_or_else(|| expr.clone())
+}
+
+fn column_type(table: Table, name: &str) -> Option<DataType> {
+    let data_type = match name {
+        "date" => DataType::Date,
+        "year" | "month" | "day" => DataType::Number,
+        "type" | "flag" | "payee" | "narration" | "description" | "account" => DataType::String,
+        "tags" | "links" => DataType::Set,
+        _ if table == Table::Entries => return None,
+        "position" => DataType::Position,
+        "number" | "cost_number" => DataType::Number,
+        "currency" | "cost_currency" => DataType::String,
+        "price" | "weight" => DataType::Amount,
+        "balance" => DataType::Inventory,
+        _ => return None,
+    };
+    Some(data_type)
+}
+
+/// static type of expression, checking columns and function arguments
+fn data_type(expr: &Expr, table: Table) -> Result<DataType, BeanCountError> {
+    let invalid = |message: String| Err(BeanCountError::InvalidQuery(message));
+    match expr {
+        Expr::Column(name) => column_type(table, name)
+            .ok_or_else(|| BeanCountError::InvalidQuery(format!("column {} is unknown", name))),
+        Expr::Literal(value) => Ok(value.data_type()),
+        Expr::Function(name, args) => {
+            if functions::is_aggregate(name) && args.iter().any(|arg| arg.is_aggregate()) {
+                return invalid(format!("aggregate functions are nested in {}", expr));
+            }
+            let args = args
+                .iter()
+                .map(|arg| data_type(arg, table))
+                .collect::<Result<Vec<DataType>, BeanCountError>>()?;
+            functions::data_type(name, &args)
+        }
+        Expr::Unary(UnaryOp::Not, inner) => data_type(inner, table).map(|_| DataType::Boolean),
+        Expr::Unary(UnaryOp::Neg, inner) => match data_type(inner, table)? {
+            data_type @ DataType::Number | data_type @ DataType::Amount => Ok(data_type),
+            _ => invalid(format!("{} cannot be negated", inner)),
+        },
+        Expr::Binary(op, left, right) => {
+            let types = (data_type(left, table)?, data_type(right, table)?);
+            match op {
+                BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div => {
+                    match (op, types) {
+                        (_, (DataType::Number, DataType::Number)) => Ok(DataType::Number),
+                        (BinaryOp::Add, (DataType::Amount, DataType::Amount))
+                        | (BinaryOp::Sub, (DataType::Amount, DataType::Amount))
+                        | (BinaryOp::Mul, (DataType::Amount, DataType::Number))
+                        | (BinaryOp::Mul, (DataType::Number, DataType::Amount))
+                        | (BinaryOp::Div, (DataType::Amount, DataType::Number)) => {
+                            Ok(DataType::Amount)
+                        }
+                        _ => invalid(format!("operands of {} are not numbers", expr)),
+                    }
+                }
+                BinaryOp::Match if types != (DataType::String, DataType::String) => {
+                    invalid(format!("operands of {} are not strings", expr))
+                }
+                BinaryOp::In | BinaryOp::NotIn if types.1 != DataType::Set => {
+                    invalid(format!("right operand of {} is not a set", expr))
+                }
+                _ => Ok(DataType::Boolean),
+            }
+        }
+    }
+}
+
+/// a row of `postings` or `entries` table
+#[derive(Debug, Clone)]
+struct Row<'a> {
+    entry: Option<&'a Directive>,
+    posting: Option<&'a Posting>,
+    /// running balance of selected postings including this one
+    balance: Option<Inventory>,
+}
+
+impl<'a> Row<'a> {
+    fn new(entry: &'a Directive, posting: Option<&'a Posting>, balance: Option<Inventory>) -> Self {
+        Row {
+            entry: Some(entry),
+            posting,
+            balance,
+        }
+    }
+
+    fn column(&self, name: &str) -> Value {
+        let transaction = match self.entry {
+            Some(Directive::Transaction(transaction)) => Some(transaction),
+            _ => None,
+        };
+        let date = self.entry.and_then(|entry| entry.date());
+        let string = |value: Option<String>| value.map(Value::String).unwrap_or(Value::Null);
+        match name {
+            "date" => date.map(|date| Value::Date(*date)).unwrap_or(Value::Null),
+            "year" => date
+                .map(|date| Value::Number(BigDecimal::from(date.year())))
+                .unwrap_or(Value::Null),
+            "month" => date
+                .map(|date| Value::Number(BigDecimal::from(date.month())))
+                .unwrap_or(Value::Null),
+            "day" => date
+                .map(|date| Value::Number(BigDecimal::from(date.day())))
+                .unwrap_or(Value::Null),
+            "type" => string(self.entry.map(|entry| type_name(entry).to_owned())),
+            "flag" => string(transaction.map(|t| t.flag.to_string())),
+            "payee" => string(transaction.and_then(|t| t.payee.clone())),
+            "narration" => string(transaction.and_then(|t| t.narration.clone())),
+            "description" => string(transaction.map(|t| {
+                let parts: Vec<&str> = t
+                    .payee
+                    .iter()
+                    .chain(t.narration.iter())
+                    .map(|part| part.as_str())
+                    .collect();
+                parts.join(" | ")
+            })),
+            "tags" => Value::Set(transaction.map(|t| t.tags.clone()).unwrap_or_default()),
+            "links" => Value::Set(transaction.map(|t| t.links.clone()).unwrap_or_default()),
+            "account" => string(match self.posting {
+                Some(posting) => Some(posting.account.to_string()),
+                None => self.entry.and_then(entry_account).map(|a| a.to_string()),
+            }),
+            _ => match self.posting {
+                Some(posting) => posting_column(posting, self.balance.as_ref(), name),
+                None => Value::Null,
+            },
+        }
+    }
+}
+
+fn posting_column(posting: &Posting, balance: Option<&Inventory>, name: &str) -> Value {
+    match name {
+        "position" => Value::Position(posting.position()),
+        "number" => Value::Number(posting.units.0.clone()),
+        "currency" => Value::String(posting.units.1.clone()),
+        "cost_number" => posting
+            .cost
+            .as_ref()
+            .map(|cost| Value::Number(cost.0.clone()))
+            .unwrap_or(Value::Null),
+        "cost_currency" => posting
+            .cost
+            .as_ref()
+            .map(|cost| Value::String(cost.1.clone()))
+            .unwrap_or(Value::Null),
+        "price" => posting
+            .price
+            .clone()
+            .map(Value::Amount)
+            .unwrap_or(Value::Null),
+        "weight" => Value::Amount(posting.weight.clone()),
+        "balance" => Value::Inventory(balance.cloned().unwrap_or_default()),
+        _ => Value::Null,
+    }
+}
+
+fn type_name(entry: &Directive) -> &'static str {
+    match entry {
+        Directive::Open { .. } => "open",
+        Directive::Close { .. } => "close",
+        Directive::Commodity { .. } => "commodity",
+        Directive::Transaction(_) => "transaction",
+        Directive::Balance { .. } => "balance",
+        Directive::Pad { .. } => "pad",
+        Directive::Note { .. } => "note",
+        Directive::Document { .. } => "document",
+        Directive::Price { .. } => "price",
+        Directive::Event { .. } => "event",
+        Directive::Custom { .. } => "custom",
+        Directive::Option { .. } => "option",
+        Directive::Plugin { .. } => "plugin",
+        Directive::Include { .. } => "include",
+        Directive::Comment { .. } => "comment",
+    }
+}
+
+fn entry_account(entry: &Directive) -> Option<&Account> {
+    match entry {
+        Directive::Open { account, .. }
+        | Directive::Close { account, .. }
+        | Directive::Balance { account, .. }
+        | Directive::Note { account, .. }
+        | Directive::Document { account, .. } => Some(account),
+        Directive::Pad { from, .. } => Some(from),
+        _ => None,
+    }
+}
+
+struct Context<'p> {
+    prices: &'p PriceMap,
+    names: &'p AccountNames,
+    regexes: RefCell<HashMap<String, Regex>>,
+}
+
+/// accumulated aggregates of a group and the index of the next one to read
+type Aggregates<'g> = (&'g [Accumulator], &'g mut usize);
+
+impl<'p> Context<'p> {
+    fn filter(&self, where_clause: &Option<Expr>, row: &Row) -> Result<bool, BeanCountError> {
+        match where_clause {
+            Some(expr) => Ok(self.evaluate(expr, row, None)?.is_true()),
+            None => Ok(true),
+        }
+    }
+
+    /// evaluate expression on row, aggregate calls read the accumulated values when given
+    fn evaluate(
+        &self,
+        expr: &Expr,
+        row: &Row,
+        mut aggregates: Option<Aggregates>,
+    ) -> Result<Value, BeanCountError> {
+        let value = match expr {
+            Expr::Column(name) => row.column(name),
+            Expr::Literal(value) => value.clone(),
+            Expr::Function(name, _) if functions::is_aggregate(name) => match aggregates {
+                Some((accumulators, index)) => {
+                    let value = accumulators[*index].value();
+                    *index += 1;
+                    value
+                }
+                None => Value::Null,
+            },
+            Expr::Function(name, args) => {
+                let mut values = vec![];
+                for arg in args {
+                    values.push(self.evaluate(arg, row, reborrow(&mut aggregates))?);
+                }
+                functions::call(name, &values, self.prices, self.names)
+            }
+            Expr::Unary(UnaryOp::Not, inner) => {
+                Value::Boolean(!self.evaluate(inner, row, aggregates)?.is_true())
+            }
+            Expr::Unary(UnaryOp::Neg, inner) => match self.evaluate(inner, row, aggregates)? {
+                Value::Number(number) => Value::Number(-number),
+                Value::Amount((number, currency)) => Value::Amount((-number, currency)),
+                _ => Value::Null,
+            },
+            Expr::Binary(op, left, right) => {
+                let left = self.evaluate(left, row, reborrow(&mut aggregates))?;
+                let right = self.evaluate(right, row, aggregates)?;
+                self.binary(*op, left, right)?
+            }
+        };
+        Ok(value)
+    }
+
+    fn binary(&self, op: BinaryOp, left: Value, right: Value) -> Result<Value, BeanCountError> {
+        let comparable = left.data_type() == right.data_type() && left != Value::Null;
+        let value = match op {
+            BinaryOp::And => Value::Boolean(left.is_true() && right.is_true()),
+            BinaryOp::Or => Value::Boolean(left.is_true() || right.is_true()),
+            BinaryOp::Eq => Value::Boolean(comparable && left == right),
+            BinaryOp::NotEq => Value::Boolean(comparable && left != right),
+            BinaryOp::Lt => Value::Boolean(comparable && left.compare(&right) == Ordering::Less),
+            BinaryOp::LtEq => {
+                Value::Boolean(comparable && left.compare(&right) != Ordering::Greater)
+            }
+            BinaryOp::Gt => Value::Boolean(comparable && left.compare(&right) == Ordering::Greater),
+            BinaryOp::GtEq => Value::Boolean(comparable && left.compare(&right) != Ordering::Less),
+            BinaryOp::Match => match (left, right) {
+                (Value::String(text), Value::String(pattern)) => {
+                    let mut regexes = self.regexes.borrow_mut();
+                    if !regexes.contains_key(&pattern) {
+                        let regex = Regex::new(&pattern).map_err(|_| {
+                            BeanCountError::InvalidQuery(format!("regex {} is invalid", pattern))
+                        })?;
+                        regexes.insert(pattern.clone(), regex);
+                    }
+                    Value::Boolean(regexes[&pattern].is_match(&text))
+                }
+                _ => Value::Boolean(false),
+            },
+            BinaryOp::In | BinaryOp::NotIn => match (left, right) {
+                (Value::String(item), Value::Set(set)) => {
+                    Value::Boolean(set.contains(&item) == (op == BinaryOp::In))
+                }
+                _ => Value::Boolean(false),
+            },
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div => {
+                arithmetic(op, left, right)
+            }
+        };
+        Ok(value)
+    }
+
+    /// group rows and evaluate items once per group
+    fn aggregate(
+        &self,
+        items: &[Expr],
+        group_by: &[Expr],
+        rows: &[Row],
+        table: Table,
+    ) -> Result<Vec<Vec<Value>>, BeanCountError> {
+        let keys: Vec<Expr> = if group_by.is_empty() {
+            items
+                .iter()
+                .filter(|item| !item.is_aggregate())
+                .cloned()
+                .collect()
+        } else {
+            for item in items {
+                if !item.is_aggregate() && !is_constant(item) && !group_by.contains(item) {
+                    return Err(BeanCountError::InvalidQuery(format!(
+                        "{} should be aggregated or appear in GROUP BY",
+                        item
+                    )));
+                }
+            }
+            group_by.to_vec()
+        };
+        let mut calls = vec![];
+        for item in items {
+            aggregate_calls(item, &mut calls);
+        }
+        let accumulators = calls
+            .iter()
+            .map(|(name, arg)| Ok(Accumulator::new(name, data_type(arg, table)?)))
+            .collect::<Result<Vec<Accumulator>, BeanCountError>>()?;
+
+        // groups in order of their first row
+        let mut groups: IndexMap<Vec<Value>, (&Row, Vec<Accumulator>)> = IndexMap::new();
+        for row in rows {
+            let key = keys
+                .iter()
+                .map(|key| self.evaluate(key, row, None))
+                .collect::<Result<Vec<Value>, BeanCountError>>()?;
+            let (_, group) = groups
+                .entry(key)
+                .or_insert_with(|| (row, accumulators.clone()));
+            for ((_, arg), accumulator) in calls.iter().zip(group.iter_mut()) {
+                accumulator.update(self.evaluate(arg, row, None)?);
+            }
+        }
+        let empty = Row {
+            entry: None,
+            posting: None,
+            balance: None,
+        };
+        if groups.is_empty() && keys.is_empty() {
+            groups.insert(vec![], (&empty, accumulators));
+        }
+
+        let mut results = vec![];
+        for (row, accumulators) in groups.values() {
+            let mut values = vec![];
+            let mut index = 0;
+            for item in items {
+                values.push(self.evaluate(item, row, Some((accumulators, &mut index)))?);
+            }
+            results.push(values);
+        }
+        Ok(results)
+    }
+}
+
+fn reborrow<'a>(aggregates: &'a mut Option<Aggregates>) -> Option<Aggregates<'a>> {
+    aggregates
+        .as_mut()
+        .map(|(accumulators, index)| (*accumulators, &mut **index))
+}
+
+/// aggregate function calls in the order `evaluate` visits them
+fn aggregate_calls(expr: &Expr, calls: &mut Vec<(String, Expr)>) {
+    match expr {
+        Expr::Function(name, args) if functions::is_aggregate(name) => {
+            let arg = args
+                .first()
+                .cloned()
+                .unwrap_or(Expr::Literal(Value::Boolean(true)));
+            calls.push((name.clone(), arg));
+        }
+        Expr::Function(_, args) => args.iter().for_each(|arg| aggregate_calls(arg, calls)),
+        Expr::Unary(_, inner) => aggregate_calls(inner, calls),
+        Expr::Binary(_, left, right) => {
+            aggregate_calls(left, calls);
+            aggregate_calls(right, calls);
+        }
+        Expr::Column(_) | Expr::Literal(_) => {}
+    }
+}
+
+fn is_constant(expr: &Expr) -> bool {
+    match expr {
+        Expr::Column(_) => false,
+        Expr::Literal(_) => true,
+        Expr::Function(_, args) => args.iter().all(is_constant),
+        Expr::Unary(_, inner) => is_constant(inner),
+        Expr::Binary(_, left, right) => is_constant(left) && is_constant(right),
+    }
+}
+
+fn arithmetic(op: BinaryOp, left: Value, right: Value) -> Value {
+    match (op, left, right) {
+        (_, Value::Number(_), Value::Number(b)) if op == BinaryOp::Div && b.is_zero() => {
+            Value::Null
+        }
+        (BinaryOp::Add, Value::Number(a), Value::Number(b)) => Value::Number(a + b),
+        (BinaryOp::Sub, Value::Number(a), Value::Number(b)) => Value::Number(a - b),
+        (BinaryOp::Mul, Value::Number(a), Value::Number(b)) => Value::Number(a * b),
+        (BinaryOp::Div, Value::Number(a), Value::Number(b)) => Value::Number(a / b),
+        (BinaryOp::Add, Value::Amount(a), Value::Amount(b)) if a.1 == b.1 => {
+            Value::Amount((a.0 + b.0, a.1))
+        }
+        (BinaryOp::Sub, Value::Amount(a), Value::Amount(b)) if a.1 == b.1 => {
+            Value::Amount((a.0 - b.0, a.1))
+        }
+        (BinaryOp::Mul, Value::Amount(a), Value::Number(b))
+        | (BinaryOp::Mul, Value::Number(b), Value::Amount(a)) => Value::Amount((a.0 * b, a.1)),
+        (BinaryOp::Div, Value::Amount(a), Value::Number(b)) if !b.is_zero() => {
+            Value::Amount((a.0 / b, a.1))
+        }
+        _ => Value::Null,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        loader::load,
+        query::{ast::Query, execute, DataType, QueryResult, Value},
+    };
+    use bigdecimal::BigDecimal;
+    use chrono::NaiveDate;
+    use std::str::FromStr;
+
+    const LEDGER: &str = r#"2020-01-01 open Assets:Bank
+2020-01-01 open Assets:Broker
+2020-01-01 open Income:Salary
+2020-01-01 open Expenses:Food
+2020-01-01 open Expenses:Travel
+2020-01-10 * "Employer" "Salary" #work
+  Assets:Bank 1000 USD
+  Income:Salary
+2020-02-10 * "Shop" "Lunch"
+  Assets:Bank -20 USD
+  Expenses:Food
+2020-02-15 * "Buy"
+  Assets:Broker 2 HOOL {100 USD}
+  Assets:Bank
+2020-03-01 * "Airline" "Flight" #trip
+  Assets:Bank -300 USD
+  Expenses:Travel
+2021-01-05 * "Shop" "Dinner"
+  Assets:Bank -30 USD
+  Expenses:Food
+2021-01-10 price HOOL 120 USD
+"#;
+
+    fn run(query: &str) -> QueryResult {
+        let ledger = load(LEDGER).unwrap();
+        execute(query, &ledger.directives, &ledger.options).unwrap()
+    }
+
+    fn number(n: i64) -> Value {
+        Value::Number(BigDecimal::from(n))
+    }
+
+    fn string(s: &str) -> Value {
+        Value::String(s.to_owned())
+    }
+
+    #[test]
+    fn parse_query() {
+        let query = Query::from_str(
+            "select distinct account AS name from year = 2020 open on 2020-01-01 clear \
+             where number > 0 group by 1 order by name desc limit 3",
+        )
+        .unwrap();
+        assert!(query.distinct);
+        assert_eq!(Some("name".to_owned()), query.targets[0].alias);
+        assert_eq!(NaiveDate::from_ymd_opt(2020, 1, 1), query.from.open);
+        assert!(query.from.clear);
+        assert_eq!(Some(3), query.limit);
+        assert_eq!("number > 0", query.where_clause.unwrap().to_string());
+        assert!(Query::from_str("select from where").is_err());
+    }
+
+    #[test]
+    fn select_postings() {
+        let result = run(
+            "SELECT date, account, number WHERE account ~ '^Expenses' AND year = 2020 \
+             ORDER BY number DESC",
+        );
+        assert_eq!(
+            vec![DataType::Date, DataType::String, DataType::Number],
+            result
+                .columns
+                .iter()
+                .map(|c| c.data_type)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![
+                vec![
+                    Value::Date(NaiveDate::from_ymd_opt(2020, 3, 1).unwrap()),
+                    string("Expenses:Travel"),
+                    number(300)
+                ],
+                vec![
+                    Value::Date(NaiveDate::from_ymd_opt(2020, 2, 10).unwrap()),
+                    string("Expenses:Food"),
+                    number(20)
+                ],
+            ],
+            result.rows
+        );
+    }
+
+    #[test]
+    fn group_and_aggregate() {
+        let result = run(
+            "SELECT account, count(*) AS n, sum(number) AS total WHERE account ~ 'Expenses' \
+             GROUP BY account ORDER BY total DESC",
+        );
+        assert_eq!("n", result.columns[1].name);
+        assert_eq!(
+            vec![
+                vec![string("Expenses:Travel"), number(1), number(300)],
+                vec![string("Expenses:Food"), number(2), number(50)],
+            ],
+            result.rows
+        );
+
+        let result = run("SELECT year, sum(number) WHERE 'trip' IN tags GROUP BY year");
+        assert_eq!(vec![vec![number(2020), number(0)]], result.rows);
+
+        let result = run("SELECT count(*) WHERE account = 'Assets:Nothing'");
+        assert_eq!(vec![vec![number(0)]], result.rows);
+    }
+
+    #[test]
+    fn positions_and_functions() {
+        let result = run(
+            "SELECT units(sum(position)), cost(sum(position)), value(sum(position)) \
+             WHERE account = 'Assets:Broker'",
+        );
+        assert_eq!(
+            vec![vec![
+                Value::String("2 HOOL".to_owned()),
+                Value::String("200 USD".to_owned()),
+                Value::String("240 USD".to_owned()),
+            ]],
+            result
+                .rows
+                .iter()
+                .map(|row| row.iter().map(|v| Value::String(v.to_string())).collect())
+                .collect::<Vec<Vec<Value>>>()
+        );
+
+        let result = run(
+            "SELECT DISTINCT account_sortkey(account) AS key, parent(account), leaf(account) \
+             ORDER BY key LIMIT 2",
+        );
+        assert_eq!(
+            vec![
+                vec![string("0-Assets:Bank"), string("Assets"), string("Bank")],
+                vec![
+                    string("0-Assets:Broker"),
+                    string("Assets"),
+                    string("Broker")
+                ],
+            ],
+            result.rows
+        );
+    }
+
+    #[test]
+    fn running_balance() {
+        let result = run("SELECT balance WHERE account = 'Assets:Bank'");
+        assert_eq!("450 USD", result.rows.last().unwrap()[0].to_string());
+    }
+
+    #[test]
+    fn entries_table() {
+        let result = run("SELECT type, count(*) FROM entries GROUP BY type ORDER BY type");
+        assert_eq!(
+            vec![
+                vec![string("open"), number(5)],
+                vec![string("price"), number(1)],
+                vec![string("transaction"), number(5)],
+            ],
+            result.rows
+        );
+    }
+
+    #[test]
+    fn open_close_clear() {
+        let result = run("SELECT account, sum(number) FROM OPEN ON 2021-01-01 \
+             WHERE account ~ '^(Equity|Expenses)' GROUP BY account ORDER BY account");
+        assert_eq!(
+            vec![
+                vec![string("Equity:Earnings:Previous"), number(-680)],
+                vec![string("Equity:Opening-Balances"), number(0)],
+                vec![string("Expenses:Food"), number(30)],
+            ],
+            result.rows
+        );
+
+        let result = run(
+            "SELECT sum(number) FROM year = 2020 CLOSE ON 2020-03-01 CLEAR \
+             WHERE account ~ '^Equity'",
+        );
+        assert_eq!(vec![vec![number(-980)]], result.rows);
+    }
+
+    #[test]
+    fn invalid_queries() {
+        let ledger = load(LEDGER).unwrap();
+        for query in &[
+            "SELECT nothing",
+            "SELECT position FROM entries",
+            "SELECT sum(sum(number))",
+            "SELECT account, sum(number) GROUP BY year",
+            "SELECT year(account)",
+            "SELECT value(units(position))",
+        ] {
+            assert!(execute(query, &ledger.directives, &ledger.options).is_err());
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use crate::{
+    booking::book,
+    error::BeanCountError,
+    inventory::Inventory,
+    models::{Account, Directive},
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// a node of account tree holding the balance of postings to exactly this account
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct RealAccount {
+    /// full account name, empty for the root of tree
+    pub name: String,
+    pub balance: Inventory,
+    pub children: BTreeMap<String, RealAccount>,
+}
+
+impl RealAccount {
+    pub fn get(&self, account: &Account) -> Option<&RealAccount> {
+        path(account)
+            .iter()
+            .try_fold(self, |node, component| node.children.get(component))
+    }
+
+    pub fn get_or_create(&mut self, account: &Account) -> &mut RealAccount {
+        let mut node = self;
+        for (depth, component) in path(account).into_iter().enumerate() {
+            let name = if depth == 0 {
+                account.root().to_owned()
+            } else {
+                format!("{}:{}", node.name, component)
+            };
+            node = node
+                .children
+                .entry(component)
+                .or_insert_with(|| RealAccount {
+                    name,
+                    ..RealAccount::default()
+                });
+        }
+        node
+    }
+
+    /// balance of this account and all its descendants
+    pub fn total(&self) -> Inventory {
+        let mut total = self.balance.clone();
+        for child in self.children.values() {
+            total.add_inventory(&child.total());
+        }
+        total
+    }
+
+    /// this account and all descendants in pre-order
+    pub fn iter(&self) -> Vec<&RealAccount> {
+        let mut accounts = vec![self];
+        for child in self.children.values() {
+            accounts.extend(child.iter());
+        }
+        accounts
+    }
+}
+
+/// children are keyed by account type at the top, so lookups do not depend on root names
+fn path(account: &Account) -> Vec<String> {
+    let mut path = vec![account.account_type().to_string()];
+    path.extend(account.components().iter().cloned());
+    path
+}
+
+/// build the account tree from opened accounts and postings of transactions
+/// ```rust
+/// use beancount::{loader::load, models::{Account, AccountType}, realization::realize};
+/// use bigdecimal::BigDecimal;
+/// let ledger = load("1970-01-01 * \"Lunch\"\n  Assets:Cash -10 USD\n  Expenses:Food\n").unwrap();
+/// let root = realize(&ledger.directives).unwrap();
+/// let expenses = root.get(&Account::new(AccountType::Expenses, vec![])).unwrap();
+/// assert_eq!(BigDecimal::from(10), expenses.total().units_of("USD"));
+/// ```
+pub fn realize(directives: &[Directive]) -> Result<RealAccount, BeanCountError> {
+    let mut root = RealAccount::default();
+    for directive in directives {
+        match directive {
+            Directive::Open { account, .. } => {
+                root.get_or_create(account);
+            }
+            Directive::Transaction(transaction) => {
+                for posting in book(transaction)? {
+                    root.get_or_create(&posting.account)
+                        .balance
+                        .add(posting.position());
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(root)
+}
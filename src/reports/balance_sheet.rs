@@ -0,0 +1,430 @@
+use crate::{
+    booking::pad,
+    error::BeanCountError,
+    inventory::Inventory,
+    models::{Account, AccountType, Directive},
+    options::Options,
+    prices::PriceMap,
+    realization::{realize, RealAccount},
+    reports::{currency_headers, inventory_cells, AccountNode, Report, Table},
+};
+use chrono::NaiveDate;
+use serde::Serialize;
+
+/// how positions held at cost are valued
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
+pub enum Valuation {
+    Cost,
+    Market,
+}
+
+/// balances of Assets, Liabilities and Equity at a date
+#[derive(Debug, PartialEq, Serialize)]
+pub struct BalanceSheet {
+    pub date: NaiveDate,
+    pub valuation: Valuation,
+    pub currencies: Vec<String>,
+    pub assets: AccountNode,
+    pub liabilities: AccountNode,
+    /// equity including synthetic earnings and conversions accounts
+    pub equity: AccountNode,
+    /// what Assets, Liabilities and Equity do not balance by, like positions without price at
+    /// market value, empty for a sound ledger
+    pub imbalance: Inventory,
+}
+
+impl BalanceSheet {
+    /// build balance sheet at the end of `date`
+    ///
+    /// Income and Expenses are transferred to `account_current_earnings`, the part before `begin`
+    /// goes to `account_previous_earnings` instead. Residual caused by currency conversions is
+    /// booked to `account_current_conversions` so that the sheet balances at cost. At market
+    /// value, amounts are converted into operating currencies and market value minus cost of
+    /// positions held at cost is booked to `account_unrealized_gains`, whatever remains
+    /// unbalanced is left in `imbalance`.
+    pub fn new(
+        directives: &[Directive],
+        options: &Options,
+        begin: Option<NaiveDate>,
+        date: NaiveDate,
+        valuation: Valuation,
+    ) -> Result<Self, BeanCountError> {
+        let directives: Vec<Directive> = pad(directives)?
+            .into_iter()
+            .filter(|directive| directive.date().is_none_or(|d| d <= &date))
+            .collect();
+        let mut root = realize(&directives)?;
+
+        let earnings = income_and_expenses(&root);
+        let previous = match begin {
+            Some(begin) => {
+                let before: Vec<Directive> = directives
+                    .iter()
+                    .filter(|directive| directive.date().is_none_or(|d| d < &begin))
+                    .cloned()
+                    .collect();
+                income_and_expenses(&realize(&before)?)
+            }
+            None => Inventory::new(),
+        };
+        let mut current = earnings;
+        current.add_inventory(&previous.neg());
+        add_synthetic(
+            &mut root,
+            &options.equity_account(&options.account_previous_earnings),
+            &previous,
+        );
+        add_synthetic(
+            &mut root,
+            &options.equity_account(&options.account_current_earnings),
+            &current,
+        );
+
+        let mut residual = Inventory::new();
+        for account_type in &[
+            AccountType::Assets,
+            AccountType::Liabilities,
+            AccountType::Equity,
+        ] {
+            if let Some(node) = root.get(&Account::new(account_type.clone(), vec![])) {
+                residual.add_inventory(&node.total().at_cost());
+            }
+        }
+        add_synthetic(
+            &mut root,
+            &options.equity_account(&options.account_current_conversions),
+            &residual.neg(),
+        );
+
+        let prices = PriceMap::from_directives(&directives);
+        let convert = |inventory: &Inventory| match valuation {
+            Valuation::Cost => inventory.at_cost(),
+            Valuation::Market => {
+                market_value(inventory, &options.operating_currency, &prices, &date)
+            }
+        };
+        let balance_sheet_types = [
+            AccountType::Assets,
+            AccountType::Liabilities,
+            AccountType::Equity,
+        ];
+        if valuation == Valuation::Market {
+            let mut gains = Inventory::new();
+            for account_type in &balance_sheet_types {
+                if let Some(node) = root.get(&Account::new(account_type.clone(), vec![])) {
+                    gains.add_inventory(&unrealized_gains(
+                        &node.total(),
+                        &options.operating_currency,
+                        &prices,
+                        &date,
+                    ));
+                }
+            }
+            add_synthetic(
+                &mut root,
+                &options.equity_account(&options.account_unrealized_gains),
+                &gains.neg(),
+            );
+        }
+        let mut imbalance = Inventory::new();
+        for account_type in &balance_sheet_types {
+            if let Some(node) = root.get(&Account::new(account_type.clone(), vec![])) {
+                imbalance.add_inventory(&convert(&node.total()));
+            }
+        }
+        let node = |account_type: AccountType| {
+            let account = options.account_names.account(account_type, vec![]);
+            let real = root.get(&account).cloned().unwrap_or_else(|| RealAccount {
+                name: account.to_string(),
+                ..RealAccount::default()
+            });
+            AccountNode::from_real(&real, &convert)
+        };
+
+        Ok(BalanceSheet {
+            date,
+            valuation,
+            currencies: options.operating_currency.clone(),
+            assets: node(AccountType::Assets),
+            liabilities: node(AccountType::Liabilities),
+            equity: node(AccountType::Equity),
+            imbalance,
+        })
+    }
+}
+
+fn income_and_expenses(root: &RealAccount) -> Inventory {
+    let mut total = Inventory::new();
+    for account_type in &[AccountType::Income, AccountType::Expenses] {
+        if let Some(node) = root.get(&Account::new(account_type.clone(), vec![])) {
+            total.add_inventory(&node.total());
+        }
+    }
+    total
+}
+
+/// market value of positions, converted into the first operating currency which has a price
+fn market_value(
+    inventory: &Inventory,
+    currencies: &[String],
+    prices: &PriceMap,
+    date: &NaiveDate,
+) -> Inventory {
+    let mut value = Inventory::new();
+    for position in inventory.at_value(prices, date).positions() {
+        let units = &position.units;
+        let converted = if currencies.contains(&units.1) {
+            None
+        } else {
+            currencies
+                .iter()
+                .find_map(|currency| prices.convert(units, currency, date))
+        };
+        value.add_amount(converted.unwrap_or_else(|| units.clone()));
+    }
+    value
+}
+
+/// market value minus cost of positions held at cost which have a price, converted into
+/// operating currencies like `market_value`
+fn unrealized_gains(
+    inventory: &Inventory,
+    currencies: &[String],
+    prices: &PriceMap,
+    date: &NaiveDate,
+) -> Inventory {
+    let mut gains = Inventory::new();
+    for position in inventory.positions() {
+        if let Some((_, currency)) = &position.cost {
+            if let Some(price) = prices.get(&position.units.1, currency, date) {
+                let (cost, _) = position.at_cost();
+                gains.add_amount((&position.units.0 * price - cost, currency.clone()));
+            }
+        }
+    }
+    market_value(&gains, currencies, prices, date)
+}
+
+fn add_synthetic(root: &mut RealAccount, account: &Account, inventory: &Inventory) {
+    if !inventory.is_empty() {
+        root.get_or_create(account).balance.add_inventory(inventory);
+    }
+}
+
+impl Report for BalanceSheet {
+    fn to_table(&self) -> Table {
+        let mut rows = vec![];
+        for (index, node) in [&self.assets, &self.liabilities, &self.equity]
+            .iter()
+            .enumerate()
+        {
+            if index > 0 {
+                rows.push(vec![]);
+            }
+            node.rows(&self.currencies, &mut rows);
+        }
+        if !self.imbalance.is_empty() {
+            rows.push(vec![]);
+            let mut row = vec!["Imbalance".to_owned()];
+            row.extend(inventory_cells(&self.imbalance, &self.currencies));
+            rows.push(row);
+        }
+        Table {
+            headers: currency_headers("Account", &self.currencies),
+            rows,
+            text_columns: 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        loader::load,
+        reports::{
+            balance_sheet::{BalanceSheet, Valuation},
+            Report,
+        },
+    };
+    use bigdecimal::BigDecimal;
+    use chrono::NaiveDate;
+
+    const LEDGER: &str = r#"option "operating_currency" "USD"
+2020-01-01 open Assets:Bank
+2020-01-01 open Assets:Broker
+2020-01-01 open Equity:Opening-Balances
+2020-01-01 open Income:Salary
+2020-01-01 open Expenses:Food
+2020-01-01 pad Assets:Bank Equity:Opening-Balances
+2020-01-02 balance Assets:Bank 1000 USD
+2020-01-10 * "Salary"
+  Assets:Bank 500 USD
+  Income:Salary
+2020-02-10 * "Lunch"
+  Assets:Bank -20 USD
+  Expenses:Food
+2020-02-15 * "Buy"
+  Assets:Broker 2 HOOL {100 USD}
+  Assets:Bank
+2020-02-20 price HOOL 110 USD
+"#;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2020, month, day).unwrap()
+    }
+
+    #[test]
+    fn balance_at_cost() {
+        let ledger = load(LEDGER).unwrap();
+        let sheet = BalanceSheet::new(
+            &ledger.directives,
+            &ledger.options,
+            None,
+            date(3, 1),
+            Valuation::Cost,
+        )
+        .unwrap();
+
+        assert_eq!(BigDecimal::from(1480), sheet.assets.total.units_of("USD"));
+        let equity = &sheet.equity;
+        assert_eq!(BigDecimal::from(-1480), equity.total.units_of("USD"));
+        let names: Vec<&str> = equity.children.iter().map(|c| c.account.as_str()).collect();
+        assert_eq!(vec!["Equity:Earnings", "Equity:Opening-Balances"], names);
+        assert_eq!(
+            BigDecimal::from(-480),
+            equity.children[0].total.units_of("USD")
+        );
+    }
+
+    #[test]
+    fn balance_at_market_value() {
+        let ledger = load(LEDGER).unwrap();
+        let sheet = BalanceSheet::new(
+            &ledger.directives,
+            &ledger.options,
+            None,
+            date(3, 1),
+            Valuation::Market,
+        )
+        .unwrap();
+        assert_eq!(BigDecimal::from(1500), sheet.assets.total.units_of("USD"));
+    }
+
+    #[test]
+    fn market_value_balances() {
+        let content = format!(
+            "{}2020-02-21 * \"Exchange\"\n  Assets:Bank -110 USD @ 0.5 EUR\n  Assets:Bank 55 EUR\n\
+             2020-02-25 price EUR 1.2 USD\n",
+            LEDGER
+        );
+        let ledger = load(&content).unwrap();
+        let sheet = BalanceSheet::new(
+            &ledger.directives,
+            &ledger.options,
+            None,
+            date(3, 1),
+            Valuation::Market,
+        )
+        .unwrap();
+        assert_eq!(vec!["USD"], sheet.assets.total.currencies());
+        assert_eq!(BigDecimal::from(1456), sheet.assets.total.units_of("USD"));
+        let mut total = sheet.assets.total.clone();
+        total.add_inventory(&sheet.liabilities.total);
+        total.add_inventory(&sheet.equity.total);
+        assert!(total.is_empty());
+        assert!(sheet.imbalance.is_empty());
+        let earnings = &sheet.equity.children[1];
+        let unrealized = earnings
+            .children
+            .iter()
+            .find(|c| c.account == "Equity:Earnings:Unrealized")
+            .unwrap();
+        assert_eq!(BigDecimal::from(-20), unrealized.total.units_of("USD"));
+    }
+
+    #[test]
+    fn missing_price_is_valued_at_cost() {
+        let content = format!(
+            "{}2020-02-21 * \"Buy\"\n  Assets:Broker 1 GOOG {{50 USD}}\n  Assets:Bank\n",
+            LEDGER
+        );
+        let ledger = load(&content).unwrap();
+        let sheet = BalanceSheet::new(
+            &ledger.directives,
+            &ledger.options,
+            None,
+            date(3, 1),
+            Valuation::Market,
+        )
+        .unwrap();
+        let earnings = sheet
+            .equity
+            .children
+            .iter()
+            .find(|c| c.account == "Equity:Earnings")
+            .unwrap();
+        let unrealized = earnings
+            .children
+            .iter()
+            .find(|c| c.account == "Equity:Earnings:Unrealized")
+            .unwrap();
+        assert_eq!(BigDecimal::from(-20), unrealized.total.units_of("USD"));
+        assert_eq!(BigDecimal::from(1500), sheet.assets.total.units_of("USD"));
+        assert!(sheet.imbalance.is_empty());
+    }
+
+    #[test]
+    fn previous_earnings() {
+        let ledger = load(LEDGER).unwrap();
+        let sheet = BalanceSheet::new(
+            &ledger.directives,
+            &ledger.options,
+            Some(date(2, 1)),
+            date(3, 1),
+            Valuation::Cost,
+        )
+        .unwrap();
+        let earnings = &sheet.equity.children[0];
+        let previous = earnings
+            .children
+            .iter()
+            .find(|c| c.account == "Equity:Earnings:Previous")
+            .unwrap();
+        let current = earnings
+            .children
+            .iter()
+            .find(|c| c.account == "Equity:Earnings:Current")
+            .unwrap();
+        assert_eq!(BigDecimal::from(-500), previous.total.units_of("USD"));
+        assert_eq!(BigDecimal::from(20), current.total.units_of("USD"));
+    }
+
+    #[test]
+    fn render() {
+        let ledger = load(LEDGER).unwrap();
+        let sheet = BalanceSheet::new(
+            &ledger.directives,
+            &ledger.options,
+            None,
+            date(1, 5),
+            Valuation::Cost,
+        )
+        .unwrap();
+        assert_eq!(
+            "Account               USD  Other\n\
+             ------------------  -----  -----\n\
+             Assets               1000\n\
+             \x20 Bank               1000\n\
+             \x20 Broker\n\
+             \n\
+             Liabilities\n\
+             \n\
+             Equity              -1000\n\
+             \x20 Opening-Balances  -1000\n",
+            sheet.to_text()
+        );
+        assert!(sheet
+            .to_json()
+            .contains("\"currencies\": [\n    \"USD\"\n  ]"));
+    }
+}
@@ -0,0 +1,464 @@
+use crate::{
+    booking::book,
+    error::BeanCountError,
+    models::{Account, AccountNames, Amount, CustomValue, Directive},
+    options::Options,
+    reports::{Report, Table},
+};
+use bigdecimal::{BigDecimal, Zero};
+use chrono::{Datelike, Duration, Months, NaiveDate};
+use indexmap::IndexMap;
+use serde::Serialize;
+use std::{collections::HashMap, iter::successors, str::FromStr};
+use strum_macros::EnumString;
+
+/// length of a budget or of a column of budget report
+#[derive(Debug, EnumString, PartialEq, Eq, Hash, Clone, Copy, strum_macros::Display, Serialize)]
+pub enum Period {
+    #[strum(serialize = "daily")]
+    Daily,
+    #[strum(serialize = "weekly")]
+    Weekly,
+    #[strum(serialize = "monthly")]
+    Monthly,
+    #[strum(serialize = "quarterly")]
+    Quarterly,
+    #[strum(serialize = "yearly")]
+    Yearly,
+}
+
+impl Period {
+    /// first day of the period containing date, weeks start on Monday
+    /// ```rust
+    /// use beancount::reports::budget::Period;
+    /// use chrono::NaiveDate;
+    /// let date = NaiveDate::from_ymd_opt(2020, 5, 14).unwrap();
+    /// assert_eq!(NaiveDate::from_ymd_opt(2020, 5, 11).unwrap(), Period::Weekly.start(date));
+    /// assert_eq!(NaiveDate::from_ymd_opt(2020, 4, 1).unwrap(), Period::Quarterly.start(date));
+    /// ```
+    pub fn start(&self, date: NaiveDate) -> NaiveDate {
+        let first_day = |month: u32| NaiveDate::from_ymd_opt(date.year(), month, 1).unwrap();
+        match self {
+            Period::Daily => date,
+            Period::Weekly => date - Duration::days(date.weekday().num_days_from_monday().into()),
+            Period::Monthly => first_day(date.month()),
+            Period::Quarterly => first_day((date.month() - 1) / 3 * 3 + 1),
+            Period::Yearly => first_day(1),
+        }
+    }
+
+    /// first day of the period after the one starting at `start`
+    pub fn next(&self, start: NaiveDate) -> NaiveDate {
+        let months = |months: u32| start.checked_add_months(Months::new(months)).unwrap();
+        match self {
+            Period::Daily => start + Duration::days(1),
+            Period::Weekly => start + Duration::weeks(1),
+            Period::Monthly => months(1),
+            Period::Quarterly => months(3),
+            Period::Yearly => months(12),
+        }
+    }
+}
+
+/// amount planned for an account per period, from `custom "budget" Account "period" amount`
+///
+/// A budget applies from its date until the next budget of the same account and currency.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Budget {
+    pub date: NaiveDate,
+    pub account: Account,
+    pub period: Period,
+    pub amount: Amount,
+}
+
+impl Budget {
+    /// budgets of `custom "budget"` directives, sorted by date, accounts are parsed with `names`
+    ///
+    /// Invalid entries are skipped and reported as errors.
+    pub fn from_directives(
+        directives: &[Directive],
+        names: &AccountNames,
+    ) -> (Vec<Budget>, Vec<BeanCountError>) {
+        let mut budgets = vec![];
+        let mut errors = vec![];
+        for directive in directives {
+            if let Directive::Custom {
+                date,
+                type_name,
+                values,
+                ..
+            } = directive
+            {
+                if type_name == "budget" {
+                    match Budget::parse(*date, values, names) {
+                        Some(budget) => budgets.push(budget),
+                        None => errors.push(BeanCountError::InvalidBudget(*date)),
+                    }
+                }
+            }
+        }
+        budgets.sort_by_key(|budget| budget.date);
+        (budgets, errors)
+    }
+
+    fn parse(date: NaiveDate, values: &[CustomValue], names: &AccountNames) -> Option<Budget> {
+        let (account, period, amount) = match values {
+            [account, CustomValue::String(period), CustomValue::Amount(amount)] => {
+                (account, period, amount)
+            }
+            _ => return None,
+        };
+        let account = match account {
+            CustomValue::Account(account) => account.clone(),
+            CustomValue::String(account) => names.parse_account(account).ok()?,
+            _ => return None,
+        };
+        Some(Budget {
+            date,
+            account,
+            period: Period::from_str(period).ok()?,
+            amount: amount.clone(),
+        })
+    }
+}
+
+/// budget and actual change of an account in one period
+#[derive(Debug, PartialEq, Serialize)]
+pub struct BudgetRow {
+    pub begin: NaiveDate,
+    pub account: String,
+    pub budget: Amount,
+    pub actual: Amount,
+    /// budget minus actual, negative when overspent
+    pub variance: Amount,
+    /// actual as percentage of budget, `None` when nothing is budgeted
+    pub percent: Option<BigDecimal>,
+}
+
+/// budget versus actual change of budgeted accounts, period by period
+///
+/// Actual changes of an account include those of its descendants, and each ancestor of a
+/// budgeted account gets a row. The budget of an account already covers its descendants, so a
+/// child budget only rolls up into a parent which has no budget of its own in that period.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct BudgetReport {
+    pub begin: NaiveDate,
+    pub end: NaiveDate,
+    pub period: Period,
+    pub rows: Vec<BudgetRow>,
+}
+
+impl BudgetReport {
+    /// build report between `begin` and `end`, both inclusive, with one row group per `period`
+    ///
+    /// A budget of another period length is prorated by days, so a monthly budget of 310 counts
+    /// 70 for a week of a 31 days month. Invalid budget entries are returned as errors next to
+    /// the report built from the valid ones.
+    pub fn new(
+        directives: &[Directive],
+        options: &Options,
+        begin: NaiveDate,
+        end: NaiveDate,
+        period: Period,
+    ) -> Result<(Self, Vec<BeanCountError>), BeanCountError> {
+        let (budgets, errors) = Budget::from_directives(directives, &options.account_names);
+
+        // budgeted accounts with all their ancestors, and currencies of their budgets
+        let mut accounts: Vec<(Account, String)> = vec![];
+        for budget in &budgets {
+            for account in lineage(&budget.account) {
+                let key = (account, budget.amount.1.clone());
+                if !accounts.contains(&key) {
+                    accounts.push(key);
+                }
+            }
+        }
+        accounts.sort();
+
+        let last = end + Duration::days(1);
+        let mut periods: Vec<(NaiveDate, NaiveDate)> = vec![];
+        let mut start = period.start(begin).max(begin);
+        while start < last {
+            let stop = period.next(period.start(start)).min(last);
+            periods.push((start, stop));
+            start = stop;
+        }
+
+        // (index of period, account, currency) -> own budget, when one applies in the period
+        let mut own: HashMap<(usize, &Account, &str), BigDecimal> = HashMap::new();
+        let mut grouped: IndexMap<(&Account, &str), Vec<&Budget>> = IndexMap::new();
+        for budget in &budgets {
+            grouped
+                .entry((&budget.account, budget.amount.1.as_str()))
+                .or_default()
+                .push(budget);
+        }
+        for ((account, currency), group) in grouped {
+            for (index, (start, stop)) in periods.iter().enumerate() {
+                if group.iter().any(|budget| budget.date < *stop) {
+                    own.insert((index, account, currency), prorated(&group, *start, *stop));
+                }
+            }
+        }
+        let mut actual: HashMap<(usize, Account, String), BigDecimal> = HashMap::new();
+        for directive in directives {
+            if let Directive::Transaction(transaction) = directive {
+                let postings = book(transaction)?;
+                let index = periods.partition_point(|(_, stop)| stop <= &transaction.date);
+                if index == periods.len() || transaction.date < periods[index].0 {
+                    continue;
+                }
+                for posting in postings {
+                    for ancestor in lineage(&posting.account) {
+                        *actual
+                            .entry((index, ancestor, posting.units.1.clone()))
+                            .or_default() += &posting.units.0;
+                    }
+                }
+            }
+        }
+
+        let mut rows = vec![];
+        for (index, (start, _)) in periods.iter().enumerate() {
+            // accounts are sorted, so children are planned before their parents
+            let mut planned: HashMap<(&Account, &str), BigDecimal> = HashMap::new();
+            for (account, currency) in accounts.iter().rev() {
+                let amount = match own.get(&(index, account, currency.as_str())) {
+                    Some(amount) => amount.clone(),
+                    None => accounts
+                        .iter()
+                        .filter(|(child, c)| {
+                            c == currency && child.parent().as_ref() == Some(account)
+                        })
+                        .map(|(child, c)| planned[&(child, c.as_str())].clone())
+                        .sum(),
+                };
+                planned.insert((account, currency), amount);
+            }
+            for (account, currency) in &accounts {
+                let planned = planned[&(account, currency.as_str())].clone();
+                let key = (index, account.clone(), currency.clone());
+                let actual = actual.remove(&key).unwrap_or_default();
+                let percent = if planned.is_zero() {
+                    None
+                } else {
+                    Some(round(&actual * BigDecimal::from(100) / &planned))
+                };
+                rows.push(BudgetRow {
+                    begin: *start,
+                    account: account.to_string(),
+                    variance: (&planned - &actual, currency.clone()),
+                    budget: (planned, currency.clone()),
+                    actual: (actual, currency.clone()),
+                    percent,
+                });
+            }
+        }
+
+        let report = BudgetReport {
+            begin,
+            end,
+            period,
+            rows,
+        };
+        Ok((report, errors))
+    }
+}
+
+/// account followed by all its ancestors
+fn lineage(account: &Account) -> impl Iterator<Item = Account> {
+    successors(Some(account.clone()), Account::parent)
+}
+
+/// budget in `[begin, stop)` of budgets of one account and currency sorted by date, budgets are
+/// prorated by days of their periods
+fn prorated(budgets: &[&Budget], begin: NaiveDate, stop: NaiveDate) -> BigDecimal {
+    // (index of budget, first day of its period) -> days covered
+    let mut covered: HashMap<(usize, NaiveDate), i64> = HashMap::new();
+    let mut day = begin;
+    while day < stop {
+        if let Some(index) = budgets.iter().rposition(|budget| budget.date <= day) {
+            *covered
+                .entry((index, budgets[index].period.start(day)))
+                .or_default() += 1;
+        }
+        day += Duration::days(1);
+    }
+    let total: BigDecimal = covered
+        .into_iter()
+        .map(|((index, start), days)| {
+            let budget = budgets[index];
+            let length = (budget.period.next(start) - start).num_days();
+            &budget.amount.0 * BigDecimal::from(days) / BigDecimal::from(length)
+        })
+        .sum();
+    round(total)
+}
+
+/// round to two decimals unless number is shorter, `BigDecimal::round` overflows on long
+/// fractions so they are truncated first
+fn round(number: BigDecimal) -> BigDecimal {
+    let rounded = number.with_scale(3).round(2);
+    if rounded == number {
+        number
+    } else {
+        rounded
+    }
+}
+
+impl Report for BudgetReport {
+    fn to_table(&self) -> Table {
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| {
+                vec![
+                    row.begin.to_string(),
+                    row.account.clone(),
+                    format!("{} {}", row.budget.0, row.budget.1),
+                    format!("{} {}", row.actual.0, row.actual.1),
+                    format!("{} {}", row.variance.0, row.variance.1),
+                    row.percent
+                        .as_ref()
+                        .map(|percent| format!("{}%", percent))
+                        .unwrap_or_default(),
+                ]
+            })
+            .collect();
+        Table {
+            headers: ["Period", "Account", "Budget", "Actual", "Variance", "Used"]
+                .iter()
+                .map(|header| header.to_string())
+                .collect(),
+            rows,
+            text_columns: 2,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        error::BeanCountError,
+        loader::load,
+        reports::{
+            budget::{BudgetReport, Period},
+            Report,
+        },
+    };
+    use chrono::NaiveDate;
+
+    const LEDGER: &str = r#"2020-01-01 custom "budget" Expenses:Food "monthly" 310 CNY
+2020-01-01 custom "budget" Expenses:Food:Coffee "weekly" 7 CNY
+2020-02-01 custom "budget" Expenses:Food "monthly" 290 CNY
+2020-01-05 * "Lunch"
+  Assets:Bank -100 CNY
+  Expenses:Food
+2020-01-20 * "Coffee"
+  Assets:Bank -30 CNY
+  Expenses:Food:Coffee
+2020-02-03 * "Dinner"
+  Assets:Bank -350 CNY
+  Expenses:Food
+"#;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2020, month, day).unwrap()
+    }
+
+    #[test]
+    fn budget_versus_actual() {
+        let ledger = load(LEDGER).unwrap();
+        let report = BudgetReport::new(
+            &ledger.directives,
+            &ledger.options,
+            date(1, 1),
+            date(2, 29),
+            Period::Monthly,
+        )
+        .unwrap()
+        .0;
+        assert_eq!(
+            "Period      Account                Budget   Actual  Variance     Used\n\
+             ----------  --------------------  -------  -------  --------  -------\n\
+             2020-01-01  Expenses              310 CNY  130 CNY   180 CNY   41.94%\n\
+             2020-01-01  Expenses:Food         310 CNY  130 CNY   180 CNY   41.94%\n\
+             2020-01-01  Expenses:Food:Coffee   31 CNY   30 CNY     1 CNY   96.77%\n\
+             2020-02-01  Expenses              290 CNY  350 CNY   -60 CNY  120.69%\n\
+             2020-02-01  Expenses:Food         290 CNY  350 CNY   -60 CNY  120.69%\n\
+             2020-02-01  Expenses:Food:Coffee   29 CNY    0 CNY    29 CNY       0%\n",
+            report.to_text()
+        );
+    }
+
+    #[test]
+    fn prorate_into_weeks() {
+        let ledger = load(LEDGER).unwrap();
+        let report = BudgetReport::new(
+            &ledger.directives,
+            &ledger.options,
+            date(1, 6),
+            date(1, 12),
+            Period::Weekly,
+        )
+        .unwrap()
+        .0;
+        let food = &report.rows[1];
+        assert_eq!("Expenses:Food", food.account);
+        assert_eq!("70", food.budget.0.to_string());
+    }
+
+    #[test]
+    fn custom_account_names() {
+        let content = LEDGER.replace("Expenses", "Spending");
+        let content = format!("option \"name_expenses\" \"Spending\"\n{}", content);
+        let ledger = load(&content).unwrap();
+        let report = BudgetReport::new(
+            &ledger.directives,
+            &ledger.options,
+            date(2, 1),
+            date(2, 29),
+            Period::Monthly,
+        )
+        .unwrap()
+        .0;
+        let rows: Vec<(&str, String, String)> = report
+            .rows
+            .iter()
+            .map(|row| {
+                (
+                    row.account.as_str(),
+                    row.budget.0.to_string(),
+                    row.actual.0.to_string(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                ("Spending", "290".to_owned(), "350".to_owned()),
+                ("Spending:Food", "290".to_owned(), "350".to_owned()),
+                ("Spending:Food:Coffee", "29".to_owned(), "0".to_owned()),
+            ],
+            rows
+        );
+    }
+
+    #[test]
+    fn invalid_budget() {
+        let content = format!(
+            "2020-01-01 custom \"budget\" Expenses:Eat \"monthly\" CNY\n{}",
+            LEDGER
+        );
+        let ledger = load(&content).unwrap();
+        let (report, errors) = BudgetReport::new(
+            &ledger.directives,
+            &ledger.options,
+            date(1, 1),
+            date(1, 31),
+            Period::Monthly,
+        )
+        .unwrap();
+        assert_eq!(vec![BeanCountError::InvalidBudget(date(1, 1))], errors);
+        assert_eq!(3, report.rows.len());
+    }
+}
@@ -0,0 +1,192 @@
+use crate::{
+    booking::pad,
+    error::BeanCountError,
+    inventory::Inventory,
+    models::{AccountType, Directive},
+    options::Options,
+    prices::PriceMap,
+    realization::{realize, RealAccount},
+    reports::{currency_headers, inventory_cells, AccountNode, Report, Table},
+};
+use chrono::NaiveDate;
+use serde::Serialize;
+
+/// Income and Expenses of a period
+#[derive(Debug, PartialEq, Serialize)]
+pub struct IncomeStatement {
+    pub begin: NaiveDate,
+    pub end: NaiveDate,
+    /// currency which amounts are converted into
+    pub currency: Option<String>,
+    pub currencies: Vec<String>,
+    pub income: AccountNode,
+    pub expenses: AccountNode,
+    /// income minus expenses, positive when the period makes profit
+    pub net_income: Inventory,
+}
+
+impl IncomeStatement {
+    /// build income statement between `begin` and `end`, both inclusive
+    ///
+    /// When `currency` is given, amounts are converted into it at the prices of `end`, those
+    /// without price stay in their own currencies.
+    pub fn new(
+        directives: &[Directive],
+        options: &Options,
+        begin: NaiveDate,
+        end: NaiveDate,
+        currency: Option<&str>,
+    ) -> Result<Self, BeanCountError> {
+        let prices = PriceMap::from_directives(directives);
+        let directives: Vec<Directive> = pad(directives)?
+            .into_iter()
+            .filter(|directive| match directive {
+                Directive::Open { date, .. } => date <= &end,
+                Directive::Transaction(transaction) => {
+                    transaction.date >= begin && transaction.date <= end
+                }
+                _ => false,
+            })
+            .collect();
+        let root = realize(&directives)?;
+
+        let convert = |inventory: &Inventory| match currency {
+            Some(currency) => inventory.convert(currency, &prices, &end),
+            None => inventory.at_cost(),
+        };
+        let node = |account_type: AccountType| {
+            let account = options.account_names.account(account_type, vec![]);
+            let real = root.get(&account).cloned().unwrap_or_else(|| RealAccount {
+                name: account.to_string(),
+                ..RealAccount::default()
+            });
+            AccountNode::from_real(&real, &convert)
+        };
+        let income = node(AccountType::Income);
+        let expenses = node(AccountType::Expenses);
+        let mut net_income = income.total.neg();
+        net_income.add_inventory(&expenses.total.neg());
+
+        Ok(IncomeStatement {
+            begin,
+            end,
+            currency: currency.map(|c| c.to_owned()),
+            currencies: match currency {
+                Some(currency) => vec![currency.to_owned()],
+                None => options.operating_currency.clone(),
+            },
+            income,
+            expenses,
+            net_income,
+        })
+    }
+}
+
+impl Report for IncomeStatement {
+    fn to_table(&self) -> Table {
+        let mut rows = vec![];
+        self.income.rows(&self.currencies, &mut rows);
+        rows.push(vec![]);
+        self.expenses.rows(&self.currencies, &mut rows);
+        rows.push(vec![]);
+        let mut net_income = vec!["Net Income".to_owned()];
+        net_income.extend(inventory_cells(&self.net_income, &self.currencies));
+        rows.push(net_income);
+        Table {
+            headers: currency_headers("Account", &self.currencies),
+            rows,
+            text_columns: 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        loader::load,
+        reports::{income_statement::IncomeStatement, Report},
+    };
+    use bigdecimal::BigDecimal;
+    use chrono::NaiveDate;
+
+    const LEDGER: &str = r#"option "operating_currency" "CNY"
+2020-01-01 open Assets:Bank
+2020-01-01 open Income:Salary
+2020-01-01 open Expenses:Food:Lunch
+2020-01-01 open Expenses:Travel
+2020-01-10 * "Salary"
+  Assets:Bank 1000 CNY
+  Income:Salary
+2020-02-10 * "Lunch"
+  Assets:Bank -20 CNY
+  Expenses:Food:Lunch
+2020-02-11 * "Dinner abroad"
+  Assets:Bank -10 USD
+  Expenses:Food
+2020-02-12 * "Flight"
+  Assets:Bank -300 CNY
+  Expenses:Travel
+2020-02-20 price USD 7 CNY
+"#;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2020, month, day).unwrap()
+    }
+
+    #[test]
+    fn period_without_conversion() {
+        let ledger = load(LEDGER).unwrap();
+        let statement = IncomeStatement::new(
+            &ledger.directives,
+            &ledger.options,
+            date(2, 1),
+            date(2, 29),
+            None,
+        )
+        .unwrap();
+
+        assert!(statement.income.total.is_empty());
+        assert_eq!(
+            BigDecimal::from(320),
+            statement.expenses.total.units_of("CNY")
+        );
+        let food = &statement.expenses.children[0];
+        assert_eq!("Expenses:Food", food.account);
+        assert_eq!(BigDecimal::from(10), food.balance.units_of("USD"));
+        assert_eq!(BigDecimal::from(20), food.total.units_of("CNY"));
+        assert_eq!(BigDecimal::from(-320), statement.net_income.units_of("CNY"));
+        assert_eq!(
+            "Account      CNY    Other\n\
+             ----------  ----  -------\n\
+             Income\n\
+             \x20 Salary\n\
+             \n\
+             Expenses     320   10 USD\n\
+             \x20 Food        20   10 USD\n\
+             \x20   Lunch     20\n\
+             \x20 Travel     300\n\
+             \n\
+             Net Income  -320  -10 USD\n",
+            statement.to_text()
+        );
+    }
+
+    #[test]
+    fn converted_into_currency() {
+        let ledger = load(LEDGER).unwrap();
+        let statement = IncomeStatement::new(
+            &ledger.directives,
+            &ledger.options,
+            date(1, 1),
+            date(2, 29),
+            Some("CNY"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            BigDecimal::from(390),
+            statement.expenses.total.units_of("CNY")
+        );
+        assert_eq!(BigDecimal::from(610), statement.net_income.units_of("CNY"));
+    }
+}
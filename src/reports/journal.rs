@@ -0,0 +1,255 @@
+use crate::{
+    booking::{book, pad},
+    error::BeanCountError,
+    inventory::Inventory,
+    models::{Account, Directive, Flag},
+    reports::{Report, Table},
+    to_file::ToBeancountFile,
+};
+use chrono::NaiveDate;
+use serde::Serialize;
+
+/// which postings are listed in journal
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct JournalFilter {
+    /// list postings to descendants of the account as well
+    pub include_children: bool,
+    pub begin: Option<NaiveDate>,
+    /// inclusive end date
+    pub end: Option<NaiveDate>,
+    pub tag: Option<String>,
+    pub link: Option<String>,
+}
+
+/// one posting of journal with the balance after it
+#[derive(Debug, PartialEq, Serialize)]
+pub struct JournalEntry {
+    pub date: NaiveDate,
+    pub flag: Flag,
+    pub payee: Option<String>,
+    pub narration: Option<String>,
+    pub account: String,
+    pub change: Inventory,
+    pub balance: Inventory,
+}
+
+/// postings of an account with running balance
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Journal {
+    pub account: String,
+    pub entries: Vec<JournalEntry>,
+}
+
+impl Journal {
+    /// build journal of account
+    ///
+    /// The running balance is the balance of the account including postings hidden by filter,
+    /// so the first listed entry carries the balance before the period.
+    pub fn new(
+        directives: &[Directive],
+        account: &Account,
+        filter: &JournalFilter,
+    ) -> Result<Self, BeanCountError> {
+        let mut balance = Inventory::new();
+        let mut entries = vec![];
+        for directive in pad(directives)? {
+            let transaction = match directive {
+                Directive::Transaction(transaction) => transaction,
+                _ => continue,
+            };
+            let listed = filter.begin.is_none_or(|begin| transaction.date >= begin)
+                && filter.end.is_none_or(|end| transaction.date <= end)
+                && filter
+                    .tag
+                    .as_ref()
+                    .is_none_or(|tag| transaction.tags.contains(tag))
+                && filter
+                    .link
+                    .as_ref()
+                    .is_none_or(|link| transaction.links.contains(link));
+            for posting in book(&transaction)? {
+                let matched = if filter.include_children {
+                    account.contains(&posting.account)
+                } else {
+                    account == &posting.account
+                };
+                if !matched {
+                    continue;
+                }
+                let mut change = Inventory::new();
+                change.add(posting.position());
+                balance.add_inventory(&change);
+                if listed {
+                    entries.push(JournalEntry {
+                        date: transaction.date,
+                        flag: transaction.flag,
+                        payee: transaction.payee.clone(),
+                        narration: transaction.narration.clone(),
+                        account: posting.account.to_string(),
+                        change,
+                        balance: balance.clone(),
+                    });
+                }
+            }
+        }
+        Ok(Journal {
+            account: account.to_string(),
+            entries,
+        })
+    }
+}
+
+impl Report for Journal {
+    fn to_table(&self) -> Table {
+        let headers = [
+            "Date",
+            "Flag",
+            "Payee",
+            "Narration",
+            "Account",
+            "Change",
+            "Balance",
+        ];
+        let rows = self
+            .entries
+            .iter()
+            .map(|entry| {
+                vec![
+                    entry.date.to_string(),
+                    entry.flag.to_text(),
+                    entry.payee.clone().unwrap_or_default(),
+                    entry.narration.clone().unwrap_or_default(),
+                    entry.account.clone(),
+                    entry.change.to_text(),
+                    entry.balance.to_text(),
+                ]
+            })
+            .collect();
+        Table {
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            rows,
+            text_columns: 5,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        loader::load,
+        models::Account,
+        reports::{
+            journal::{Journal, JournalFilter},
+            Report,
+        },
+    };
+    use bigdecimal::BigDecimal;
+    use chrono::NaiveDate;
+    use std::str::FromStr;
+
+    const LEDGER: &str = r#"2020-01-01 open Assets:Bank
+2020-01-01 open Assets:Bank:Saving
+2020-01-01 open Expenses:Food
+2020-01-02 * "Employer" "Salary" #work
+  Assets:Bank 1000 USD
+  Income:Salary
+2020-01-05 * "Shop" "Groceries" ^receipt-1
+  Assets:Bank -20.50 USD
+  Expenses:Food
+2020-01-06 ! "Saving"
+  Assets:Bank -100 USD
+  Assets:Bank:Saving 100 USD
+2020-02-01 * "Cafe, Inc" "Coffee"
+  Assets:Bank:Saving -5 USD
+  Expenses:Food
+"#;
+
+    fn journal(account: &str, filter: JournalFilter) -> Journal {
+        let ledger = load(LEDGER).unwrap();
+        Journal::new(
+            &ledger.directives,
+            &Account::from_str(account).unwrap(),
+            &filter,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn running_balance() {
+        let journal = journal("Assets:Bank", JournalFilter::default());
+        assert_eq!(3, journal.entries.len());
+        assert_eq!(
+            BigDecimal::from_str("879.50").unwrap(),
+            journal.entries[2].balance.units_of("USD")
+        );
+    }
+
+    #[test]
+    fn include_children() {
+        let journal = journal(
+            "Assets:Bank",
+            JournalFilter {
+                include_children: true,
+                ..JournalFilter::default()
+            },
+        );
+        assert_eq!(5, journal.entries.len());
+        assert_eq!(
+            BigDecimal::from_str("974.50").unwrap(),
+            journal.entries[4].balance.units_of("USD")
+        );
+    }
+
+    #[test]
+    fn filters() {
+        let by_date = journal(
+            "Assets:Bank",
+            JournalFilter {
+                begin: Some(NaiveDate::from_ymd_opt(2020, 1, 3).unwrap()),
+                ..JournalFilter::default()
+            },
+        );
+        assert_eq!(2, by_date.entries.len());
+        assert_eq!(
+            BigDecimal::from_str("979.50").unwrap(),
+            by_date.entries[0].balance.units_of("USD")
+        );
+
+        let by_tag = journal(
+            "Assets:Bank",
+            JournalFilter {
+                tag: Some("work".to_owned()),
+                ..JournalFilter::default()
+            },
+        );
+        assert_eq!(1, by_tag.entries.len());
+
+        let by_link = journal(
+            "Expenses:Food",
+            JournalFilter {
+                link: Some("receipt-1".to_owned()),
+                ..JournalFilter::default()
+            },
+        );
+        assert_eq!(1, by_link.entries.len());
+        assert_eq!(Some("Shop".to_owned()), by_link.entries[0].payee);
+    }
+
+    #[test]
+    fn render() {
+        let journal = journal("Expenses:Food", JournalFilter::default());
+        assert_eq!(
+            "Date,Flag,Payee,Narration,Account,Change,Balance\n\
+             2020-01-05,*,Shop,Groceries,Expenses:Food,20.50 USD,20.50 USD\n\
+             2020-02-01,*,\"Cafe, Inc\",Coffee,Expenses:Food,5 USD,25.50 USD\n",
+            journal.to_csv()
+        );
+        assert_eq!(
+            "Date        Flag  Payee      Narration  Account           Change    Balance\n\
+             ----------  ----  ---------  ---------  -------------  ---------  ---------\n\
+             2020-01-05  *     Shop       Groceries  Expenses:Food  20.50 USD  20.50 USD\n\
+             2020-02-01  *     Cafe, Inc  Coffee     Expenses:Food      5 USD  25.50 USD\n",
+            journal.to_text()
+        );
+    }
+}
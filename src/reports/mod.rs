@@ -0,0 +1,193 @@
+use crate::{inventory::Inventory, realization::RealAccount, to_file::ToBeancountFile};
+use bigdecimal::Zero;
+use serde::Serialize;
+
+pub mod balance_sheet;
+pub mod budget;
+pub mod income_statement;
+pub mod journal;
+pub mod trial_balance;
+
+/// rows of a report ready to be rendered
+#[derive(Debug, Default, PartialEq)]
+pub struct Table {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    /// number of leading text columns, the others hold amounts
+    pub text_columns: usize,
+}
+
+impl Table {
+    /// render as aligned text, text columns are left aligned and amount columns are right aligned
+    /// ```rust
+    /// use beancount::reports::Table;
+    /// let table = Table {
+    ///     headers: vec!["Account".to_owned(), "USD".to_owned()],
+    ///     rows: vec![vec!["Assets".to_owned(), "10.00".to_owned()]],
+    ///     text_columns: 1,
+    /// };
+    /// assert_eq!("Account    USD\n-------  -----\nAssets   10.00\n", table.to_text());
+    /// ```
+    pub fn to_text(&self) -> String {
+        let widths: Vec<usize> = (0..self.headers.len())
+            .map(|column| {
+                self.rows
+                    .iter()
+                    .chain(std::iter::once(&self.headers))
+                    .filter_map(|row| row.get(column))
+                    .map(|cell| cell.chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let separator: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+
+        let mut text = String::new();
+        for row in std::iter::once(&self.headers)
+            .chain(std::iter::once(&separator))
+            .chain(self.rows.iter())
+        {
+            let line = row
+                .iter()
+                .zip(widths.iter())
+                .enumerate()
+                .map(|(column, (cell, width))| {
+                    if column < self.text_columns {
+                        format!("{:<width$}", cell, width = width)
+                    } else {
+                        format!("{:>width$}", cell, width = width)
+                    }
+                })
+                .collect::<Vec<String>>()
+                .join("  ");
+            text.push_str(line.trim_end());
+            text.push('\n');
+        }
+        text
+    }
+
+    /// render as CSV, fields with comma, quote or line break are quoted
+    /// ```rust
+    /// use beancount::reports::Table;
+    /// let table = Table {
+    ///     headers: vec!["Payee".to_owned(), "Change".to_owned()],
+    ///     rows: vec![vec!["Shop \"A\"".to_owned(), "1 USD, 2 CNY".to_owned()]],
+    ///     text_columns: 1,
+    /// };
+    /// assert_eq!("Payee,Change\n\"Shop \"\"A\"\"\",\"1 USD, 2 CNY\"\n", table.to_csv());
+    /// ```
+    pub fn to_csv(&self) -> String {
+        std::iter::once(&self.headers)
+            .chain(self.rows.iter())
+            .map(|row| {
+                let fields: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+                format!("{}\n", fields.join(","))
+            })
+            .collect()
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// a report which can be rendered as text table or JSON
+pub trait Report: Serialize {
+    fn to_table(&self) -> Table;
+
+    fn to_text(&self) -> String {
+        self.to_table().to_text()
+    }
+
+    fn to_csv(&self) -> String {
+        self.to_table().to_csv()
+    }
+
+    fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("report should be serializable")
+    }
+}
+
+/// account tree of a report with balances already converted
+#[derive(Debug, PartialEq, Serialize)]
+pub struct AccountNode {
+    pub account: String,
+    pub balance: Inventory,
+    /// balance of account and all its descendants
+    pub total: Inventory,
+    pub children: Vec<AccountNode>,
+}
+
+impl AccountNode {
+    pub(crate) fn from_real(
+        real: &RealAccount,
+        convert: &impl Fn(&Inventory) -> Inventory,
+    ) -> Self {
+        let children: Vec<AccountNode> = real
+            .children
+            .values()
+            .map(|child| AccountNode::from_real(child, convert))
+            .collect();
+        let balance = convert(&real.balance);
+        let mut total = balance.clone();
+        for child in &children {
+            total.add_inventory(&child.total);
+        }
+        AccountNode {
+            account: real.name.clone(),
+            balance,
+            total,
+            children,
+        }
+    }
+
+    /// rows of tree with indented account names, one column per currency plus an "Other" column
+    pub(crate) fn rows(&self, currencies: &[String], rows: &mut Vec<Vec<String>>) {
+        self.push_rows(0, currencies, rows);
+    }
+
+    fn push_rows(&self, depth: usize, currencies: &[String], rows: &mut Vec<Vec<String>>) {
+        let name = self.account.rsplit(':').next().unwrap_or_default();
+        let mut row = vec![format!("{}{}", "  ".repeat(depth), name)];
+        row.extend(inventory_cells(&self.total, currencies));
+        rows.push(row);
+        for child in &self.children {
+            child.push_rows(depth + 1, currencies, rows);
+        }
+    }
+}
+
+/// headers of account column, currency columns and "Other" column
+pub(crate) fn currency_headers(first: &str, currencies: &[String]) -> Vec<String> {
+    let mut headers = vec![first.to_owned()];
+    headers.extend(currencies.iter().cloned());
+    headers.push("Other".to_owned());
+    headers
+}
+
+/// cells of inventory split into given currencies, the rest are rendered in the last cell
+pub(crate) fn inventory_cells(inventory: &Inventory, currencies: &[String]) -> Vec<String> {
+    let mut cells: Vec<String> = currencies
+        .iter()
+        .map(|currency| {
+            let number = inventory.units_of(currency);
+            if number.is_zero() {
+                String::new()
+            } else {
+                number.to_string()
+            }
+        })
+        .collect();
+    let mut other = Inventory::new();
+    for position in inventory.positions() {
+        if !currencies.contains(&position.units.1) {
+            other.add(position.clone());
+        }
+    }
+    cells.push(other.to_text());
+    cells
+}
@@ -0,0 +1,262 @@
+use crate::{
+    booking::{book, pad},
+    error::BeanCountError,
+    inventory::Inventory,
+    models::{Directive, Location},
+    options::Options,
+    realization::realize,
+    reports::{currency_headers, inventory_cells, Report, Table},
+};
+use chrono::NaiveDate;
+use serde::Serialize;
+
+/// closing balance of an account
+#[derive(Debug, PartialEq, Serialize)]
+pub struct TrialBalanceEntry {
+    pub account: String,
+    pub balance: Inventory,
+    /// account has children, the balance is of postings booked to it directly
+    pub parent: bool,
+}
+
+/// transaction whose posting weights do not sum up to zero within tolerance
+#[derive(Debug, PartialEq, Serialize)]
+pub struct UnbalancedTransaction {
+    pub date: NaiveDate,
+    pub location: Location,
+    pub narration: String,
+    pub residual: Inventory,
+}
+
+/// closing balances of all leaf accounts at cost
+///
+/// Parent accounts are listed too when postings are booked to them directly, marked as such.
+/// Balances do not sum up to zero when prices convert between currencies, so soundness is
+/// checked on the weights of postings of each transaction instead, the transactions which do
+/// not balance are listed after the accounts.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct TrialBalance {
+    pub date: Option<NaiveDate>,
+    pub currencies: Vec<String>,
+    pub entries: Vec<TrialBalanceEntry>,
+    pub unbalanced: Vec<UnbalancedTransaction>,
+}
+
+impl TrialBalance {
+    /// build trial balance at the end of `date`, or of the whole ledger when no date is given
+    pub fn new(
+        directives: &[Directive],
+        options: &Options,
+        date: Option<NaiveDate>,
+    ) -> Result<Self, BeanCountError> {
+        let directives: Vec<Directive> = pad(directives)?
+            .into_iter()
+            .filter(|directive| date.is_none_or(|date| directive.date().is_none_or(|d| d <= &date)))
+            .collect();
+        let root = realize(&directives)?;
+
+        let mut unbalanced = vec![];
+        for directive in &directives {
+            if let Directive::Transaction(transaction) = directive {
+                let mut residual = Inventory::new();
+                for posting in book(transaction)? {
+                    residual.add_amount(posting.weight);
+                }
+                let residual = beyond_tolerance(residual, options);
+                if !residual.is_empty() {
+                    unbalanced.push(UnbalancedTransaction {
+                        date: transaction.date,
+                        location: transaction.location,
+                        narration: transaction.narration.clone().unwrap_or_default(),
+                        residual,
+                    });
+                }
+            }
+        }
+
+        let mut currencies: Vec<String> = vec![];
+        let mut entries = vec![];
+        for node in root.iter() {
+            // parent accounts are listed only when postings are booked to them directly
+            if node.name.is_empty() || (!node.children.is_empty() && node.balance.is_empty()) {
+                continue;
+            }
+            let balance = node.balance.at_cost();
+            for currency in balance.currencies() {
+                if !currencies.iter().any(|c| c == currency) {
+                    currencies.push(currency.to_owned());
+                }
+            }
+            entries.push(TrialBalanceEntry {
+                account: node.name.clone(),
+                balance,
+                parent: !node.children.is_empty(),
+            });
+        }
+        currencies.sort();
+
+        Ok(TrialBalance {
+            date,
+            currencies,
+            entries,
+            unbalanced,
+        })
+    }
+
+    /// whether all transactions balance within the default tolerance
+    pub fn is_balanced(&self) -> bool {
+        self.unbalanced.is_empty()
+    }
+}
+
+/// positions of inventory exceeding the default tolerance of their currencies
+fn beyond_tolerance(inventory: Inventory, options: &Options) -> Inventory {
+    let mut result = Inventory::new();
+    for position in inventory.positions() {
+        let (number, currency) = &position.units;
+        if number.abs() > options.default_tolerance(currency) {
+            result.add(position.clone());
+        }
+    }
+    result
+}
+
+impl Report for TrialBalance {
+    fn to_table(&self) -> Table {
+        let mut rows: Vec<Vec<String>> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let account = if entry.parent {
+                    format!("{} (direct)", entry.account)
+                } else {
+                    entry.account.clone()
+                };
+                let mut row = vec![account];
+                row.extend(inventory_cells(&entry.balance, &self.currencies));
+                row
+            })
+            .collect();
+        if !self.unbalanced.is_empty() {
+            rows.push(vec![]);
+        }
+        for transaction in &self.unbalanced {
+            let mut row = vec![format!(
+                "Unbalanced {} {:?}",
+                transaction.date, transaction.narration
+            )];
+            row.extend(inventory_cells(&transaction.residual, &self.currencies));
+            rows.push(row);
+        }
+        Table {
+            headers: currency_headers("Account", &self.currencies),
+            rows,
+            text_columns: 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        loader::load,
+        models::Location,
+        reports::{trial_balance::TrialBalance, Report},
+    };
+    use bigdecimal::BigDecimal;
+    use chrono::NaiveDate;
+
+    const LEDGER: &str = r#"2020-01-01 open Assets:Bank
+2020-01-01 open Assets:Bank:Saving
+2020-01-01 open Equity:Opening-Balances
+2020-01-01 open Expenses:Food
+2020-01-01 pad Assets:Bank Equity:Opening-Balances
+2020-01-02 balance Assets:Bank 1000 USD
+2020-01-05 * "Lunch"
+  Assets:Bank:Saving -20 USD
+  Expenses:Food
+2020-02-01 * "Typo"
+  Assets:Bank:Saving -5 USD
+  Expenses:Food 50 USD
+"#;
+
+    #[test]
+    fn leaf_balances() {
+        let ledger = load(LEDGER).unwrap();
+        let date = NaiveDate::from_ymd_opt(2020, 1, 31).unwrap();
+        let trial = TrialBalance::new(&ledger.directives, &ledger.options, Some(date)).unwrap();
+
+        let accounts: Vec<&str> = trial.entries.iter().map(|e| e.account.as_str()).collect();
+        assert_eq!(
+            vec![
+                "Assets:Bank",
+                "Assets:Bank:Saving",
+                "Equity:Opening-Balances",
+                "Expenses:Food"
+            ],
+            accounts
+        );
+        assert!(trial.is_balanced());
+        assert_eq!(
+            "Account                    USD  Other\n\
+             -----------------------  -----  -----\n\
+             Assets:Bank (direct)      1000\n\
+             Assets:Bank:Saving         -20\n\
+             Equity:Opening-Balances  -1000\n\
+             Expenses:Food               20\n",
+            trial.to_text()
+        );
+    }
+
+    #[test]
+    fn unbalanced_transactions_do_not_cancel() {
+        let content = format!(
+            "{}2020-03-01 * \"Refund\"\n  Assets:Bank:Saving 5 USD\n  Expenses:Food -50 USD\n",
+            LEDGER
+        );
+        let ledger = load(&content).unwrap();
+        let trial = TrialBalance::new(&ledger.directives, &ledger.options, None).unwrap();
+        assert!(!trial.is_balanced());
+        let unbalanced: Vec<(Location, String, BigDecimal)> = trial
+            .unbalanced
+            .iter()
+            .map(|transaction| {
+                (
+                    transaction.location,
+                    transaction.narration.clone(),
+                    transaction.residual.units_of("USD"),
+                )
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                (Location(Some(10)), "Typo".to_owned(), BigDecimal::from(45)),
+                (
+                    Location(Some(13)),
+                    "Refund".to_owned(),
+                    BigDecimal::from(-45)
+                ),
+            ],
+            unbalanced
+        );
+        assert!(trial.to_text().ends_with(
+            "Unbalanced 2020-02-01 \"Typo\"       45\n\
+             Unbalanced 2020-03-01 \"Refund\"    -45\n"
+        ));
+    }
+
+    #[test]
+    fn price_conversion_is_balanced() {
+        let content = format!(
+            "{}2020-01-10 * \"Exchange\"\n  Assets:Bank -100 USD @ 7 CNY\n  Assets:Bank 700 CNY\n",
+            LEDGER
+        );
+        let ledger = load(&content).unwrap();
+        let date = NaiveDate::from_ymd_opt(2020, 1, 31).unwrap();
+        let trial = TrialBalance::new(&ledger.directives, &ledger.options, Some(date)).unwrap();
+        assert!(trial.is_balanced());
+        let bank = &trial.entries[0].balance;
+        assert_eq!(BigDecimal::from(900), bank.units_of("USD"));
+        assert_eq!(BigDecimal::from(700), bank.units_of("CNY"));
+    }
+}
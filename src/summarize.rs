@@ -0,0 +1,419 @@
+use crate::{
+    booking::{book, pad},
+    error::BeanCountError,
+    inventory::Inventory,
+    models::{sort_directives, Account, Amount, Directive, Flag, Transaction, TransactionLine},
+    options::Options,
+    to_file::ToBeancountFile,
+};
+use bigdecimal::BigDecimal;
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+
+/// limit directives to the period `[begin, end)` the way reports of a period see them
+///
+/// Income and Expenses before `begin` are transferred to `account_previous_earnings`, the balance
+/// of every account before `begin` is summarized into opening entries against
+/// `account_previous_balances`, entries from `end` on are dropped and a conversion entry is
+/// inserted into `account_current_conversions` so that all balances sum up to zero.
+pub fn clamp(
+    directives: &[Directive],
+    begin: NaiveDate,
+    end: NaiveDate,
+    options: &Options,
+) -> Result<Vec<Directive>, BeanCountError> {
+    let directives = pad(directives)?;
+    let directives = transfer_balances(
+        &directives,
+        begin,
+        |account| account.is_income() || account.is_expense(),
+        &options.equity_account(&options.account_previous_earnings),
+    )?;
+    let directives = summarize(
+        &directives,
+        begin,
+        &options.equity_account(&options.account_previous_balances),
+    )?;
+    let directives = truncate(&directives, end);
+    conversions(
+        &directives,
+        &options.equity_account(&options.account_current_conversions),
+        &options.conversion_currency,
+        end,
+    )
+}
+
+/// summarize entries before `date` into opening entries, like the beginning of a period
+pub fn open(
+    directives: &[Directive],
+    date: NaiveDate,
+    options: &Options,
+) -> Result<Vec<Directive>, BeanCountError> {
+    let directives = pad(directives)?;
+    let directives = transfer_balances(
+        &directives,
+        date,
+        |account| account.is_income() || account.is_expense(),
+        &options.equity_account(&options.account_previous_earnings),
+    )?;
+    let directives = summarize(
+        &directives,
+        date,
+        &options.equity_account(&options.account_previous_balances),
+    )?;
+    conversions(
+        &directives,
+        &options.equity_account(&options.account_previous_conversions),
+        &options.conversion_currency,
+        date,
+    )
+}
+
+/// drop entries from `date` on and insert the conversion entry of current period
+pub fn close(
+    directives: &[Directive],
+    date: NaiveDate,
+    options: &Options,
+) -> Result<Vec<Directive>, BeanCountError> {
+    let directives = truncate(directives, date);
+    conversions(
+        &directives,
+        &options.equity_account(&options.account_current_conversions),
+        &options.conversion_currency,
+        date,
+    )
+}
+
+/// transfer Income and Expenses before `date` into `account_current_earnings`
+pub fn clear(
+    directives: &[Directive],
+    date: NaiveDate,
+    options: &Options,
+) -> Result<Vec<Directive>, BeanCountError> {
+    transfer_balances(
+        directives,
+        date,
+        |account| account.is_income() || account.is_expense(),
+        &options.equity_account(&options.account_current_earnings),
+    )
+}
+
+/// move balances of accounts matching predicate before `date` into `transfer_account`
+pub fn transfer_balances(
+    directives: &[Directive],
+    date: NaiveDate,
+    predicate: impl Fn(&Account) -> bool,
+    transfer_account: &Account,
+) -> Result<Vec<Directive>, BeanCountError> {
+    let mut directives = directives.to_vec();
+    sort_directives(&mut directives);
+    let index = split_index(&directives, date);
+    let balances = balances(&directives[..index])?;
+    let entry_date = date.pred_opt().unwrap_or(date);
+
+    let transfers = balances
+        .into_iter()
+        .filter(|(account, _)| predicate(account))
+        .map(|(account, inventory)| {
+            let mut lines = vec![];
+            for position in inventory.neg().positions() {
+                lines.push(line(
+                    &account,
+                    position.units.clone(),
+                    position.cost.clone(),
+                ));
+            }
+            for amount in inventory.at_cost().positions() {
+                lines.push(line(transfer_account, amount.units.clone(), None));
+            }
+            entry(
+                entry_date,
+                Flag::Transfer,
+                format!(
+                    "Transfer balance for '{}' (Transfer balance)",
+                    account.to_string()
+                ),
+                lines,
+            )
+        });
+    directives.splice(index..index, transfers);
+    Ok(directives)
+}
+
+/// replace entries before `date` with one opening entry per account against `opening_account`
+pub fn summarize(
+    directives: &[Directive],
+    date: NaiveDate,
+    opening_account: &Account,
+) -> Result<Vec<Directive>, BeanCountError> {
+    let mut directives = directives.to_vec();
+    sort_directives(&mut directives);
+    let index = split_index(&directives, date);
+    let balances = balances(&directives[..index])?;
+    let entry_date = date.pred_opt().unwrap_or(date);
+
+    let mut summarized: Vec<Directive> = directives
+        .drain(..index)
+        .filter(|directive| {
+            !matches!(
+                directive,
+                Directive::Transaction(_)
+                    | Directive::Pad { .. }
+                    | Directive::Balance { .. }
+                    | Directive::Note { .. }
+                    | Directive::Document { .. }
+            )
+        })
+        .collect();
+    summarized.extend(balances.into_iter().map(|(account, inventory)| {
+        let mut lines = vec![];
+        for position in inventory.positions() {
+            lines.push(line(
+                &account,
+                position.units.clone(),
+                position.cost.clone(),
+            ));
+        }
+        for amount in inventory.at_cost().neg().positions() {
+            lines.push(line(opening_account, amount.units.clone(), None));
+        }
+        entry(
+            entry_date,
+            Flag::Summarize,
+            format!(
+                "Opening balance for '{}' (Summarization)",
+                account.to_string()
+            ),
+            lines,
+        )
+    }));
+    summarized.extend(directives);
+    Ok(summarized)
+}
+
+/// drop dated entries from `date` on
+pub fn truncate(directives: &[Directive], date: NaiveDate) -> Vec<Directive> {
+    directives
+        .iter()
+        .filter(|directive| directive.date().is_none_or(|d| d < &date))
+        .cloned()
+        .collect()
+}
+
+/// insert an entry before `date` which brings the total of all balances at cost back to zero
+pub fn conversions(
+    directives: &[Directive],
+    conversion_account: &Account,
+    conversion_currency: &str,
+    date: NaiveDate,
+) -> Result<Vec<Directive>, BeanCountError> {
+    let mut directives = directives.to_vec();
+    sort_directives(&mut directives);
+    let index = split_index(&directives, date);
+    let mut total = Inventory::new();
+    for inventory in balances(&directives[..index])?.values() {
+        total.add_inventory(&inventory.at_cost());
+    }
+    if total.is_empty() {
+        return Ok(directives);
+    }
+    let lines = total
+        .neg()
+        .positions()
+        .iter()
+        .map(|position| TransactionLine {
+            single_price: Some((BigDecimal::from(0), conversion_currency.to_owned())),
+            ..line(conversion_account, position.units.clone(), None)
+        })
+        .collect();
+    let conversion = entry(
+        date.pred_opt().unwrap_or(date),
+        Flag::Conversion,
+        format!("Conversion for {}", total.to_text()),
+        lines,
+    );
+    directives.insert(index, conversion);
+    Ok(directives)
+}
+
+/// index of first entry at or after `date` in sorted directives
+fn split_index(directives: &[Directive], date: NaiveDate) -> usize {
+    directives.partition_point(|directive| directive.date().is_none_or(|d| d < &date))
+}
+
+fn balances(directives: &[Directive]) -> Result<BTreeMap<Account, Inventory>, BeanCountError> {
+    let mut balances: BTreeMap<Account, Inventory> = BTreeMap::new();
+    for directive in directives {
+        if let Directive::Transaction(transaction) = directive {
+            for posting in book(transaction)? {
+                balances
+                    .entry(posting.account.clone())
+                    .or_default()
+                    .add(posting.position());
+            }
+        }
+    }
+    balances.retain(|_, inventory| !inventory.is_empty());
+    Ok(balances)
+}
+
+fn line(account: &Account, units: Amount, cost: Option<Amount>) -> TransactionLine {
+    TransactionLine {
+        flag: Flag::Complete,
+        account: account.clone(),
+        amount: Some(units),
+        cost: cost.map(|cost| (cost, None)),
+        single_price: None,
+        total_price: None,
+        comment: None,
+    }
+}
+
+fn entry(date: NaiveDate, flag: Flag, narration: String, lines: Vec<TransactionLine>) -> Directive {
+    Directive::Transaction(Transaction::new(
+        date,
+        flag,
+        None,
+        Some(narration),
+        vec![],
+        vec![],
+        lines,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        booking::book,
+        loader::load,
+        models::{Directive, Flag},
+        realization::realize,
+        summarize::clamp,
+    };
+    use bigdecimal::BigDecimal;
+    use chrono::NaiveDate;
+    use std::str::FromStr;
+
+    const LEDGER: &str = r#"2019-01-01 open Assets:Bank
+2019-01-01 open Assets:Wallet
+2019-01-01 open Income:Salary
+2019-01-01 open Expenses:Food
+2019-03-01 * "Salary"
+  Assets:Bank 1000 USD
+  Income:Salary
+2019-06-01 * "Exchange"
+  Assets:Bank -100 USD @ 7 CNY
+  Assets:Wallet 700 CNY
+2020-02-01 * "Lunch"
+  Assets:Wallet -70 CNY
+  Expenses:Food
+2020-03-01 * "Exchange"
+  Assets:Bank -10 USD @ 7 CNY
+  Assets:Wallet 70 CNY
+2021-01-05 * "Dinner"
+  Assets:Wallet -30 CNY
+  Expenses:Food
+"#;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn transactions(directives: &[Directive]) -> Vec<(NaiveDate, Flag, String)> {
+        directives
+            .iter()
+            .filter_map(|directive| match directive {
+                Directive::Transaction(t) => {
+                    Some((t.date, t.flag, t.narration.clone().unwrap_or_default()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn clamp_period() {
+        let ledger = load(LEDGER).unwrap();
+        let directives = clamp(
+            &ledger.directives,
+            date(2020, 1, 1),
+            date(2021, 1, 1),
+            &ledger.options,
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![
+                (
+                    date(2019, 12, 31),
+                    Flag::Summarize,
+                    "Opening balance for 'Assets:Bank' (Summarization)".to_owned()
+                ),
+                (
+                    date(2019, 12, 31),
+                    Flag::Summarize,
+                    "Opening balance for 'Assets:Wallet' (Summarization)".to_owned()
+                ),
+                (
+                    date(2019, 12, 31),
+                    Flag::Summarize,
+                    "Opening balance for 'Equity:Earnings:Previous' (Summarization)".to_owned()
+                ),
+                (date(2020, 2, 1), Flag::Complete, "Lunch".to_owned()),
+                (date(2020, 3, 1), Flag::Complete, "Exchange".to_owned()),
+                (
+                    date(2020, 12, 31),
+                    Flag::Conversion,
+                    "Conversion for -10 USD, 70 CNY".to_owned()
+                ),
+            ],
+            transactions(&directives)
+        );
+        assert_eq!(
+            4,
+            directives
+                .iter()
+                .filter(|d| matches!(d, Directive::Open { .. }))
+                .count()
+        );
+
+        let root = realize(&directives).unwrap();
+        let balance = |name: &str| {
+            root.get(&FromStr::from_str(name).unwrap())
+                .map(|node| node.total())
+                .unwrap_or_default()
+        };
+        assert_eq!(
+            BigDecimal::from(890),
+            balance("Assets:Bank").units_of("USD")
+        );
+        assert_eq!(
+            BigDecimal::from(700),
+            balance("Assets:Wallet").units_of("CNY")
+        );
+        assert_eq!(
+            BigDecimal::from(-1000),
+            balance("Equity:Earnings:Previous").units_of("USD")
+        );
+        let opening = balance("Equity:Opening-Balances");
+        assert_eq!(BigDecimal::from(100), opening.units_of("USD"));
+        assert_eq!(BigDecimal::from(-700), opening.units_of("CNY"));
+        let conversions = balance("Equity:Conversions:Current");
+        assert_eq!(BigDecimal::from(10), conversions.units_of("USD"));
+        assert_eq!(BigDecimal::from(-70), conversions.units_of("CNY"));
+        assert_eq!(
+            BigDecimal::from(70),
+            balance("Expenses:Food").units_of("CNY")
+        );
+        assert!(balance("Income:Salary").is_empty());
+
+        let conversion = match directives.last().unwrap() {
+            Directive::Transaction(transaction) => transaction,
+            _ => unreachable!(),
+        };
+        assert!(book(conversion)
+            .unwrap()
+            .iter()
+            .all(|posting| posting.weight.0 == BigDecimal::from(0)));
+    }
+}
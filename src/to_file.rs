@@ -1,5 +1,6 @@
 use crate::{
-    models::{Amount, Directive, Flag},
+    inventory::{Inventory, Position},
+    models::{Amount, CustomValue, Directive, Flag},
     utils::escape_with_quote,
 };
 use itertools::Itertools;
@@ -14,11 +15,43 @@ impl ToBeancountFile for Amount {
     }
 }
 
+impl ToBeancountFile for CustomValue {
+    fn to_text(&self) -> String {
+        match self {
+            CustomValue::String(string) => escape_with_quote(string).into_owned(),
+            CustomValue::Amount(amount) => amount.to_text(),
+            CustomValue::Account(account) => account.to_string(),
+            CustomValue::Word(word) => word.clone(),
+        }
+    }
+}
+
+impl ToBeancountFile for Position {
+    fn to_text(&self) -> String {
+        match &self.cost {
+            Some(cost) => format!("{} {{{}}}", self.units.to_text(), cost.to_text()),
+            None => self.units.to_text(),
+        }
+    }
+}
+
+impl ToBeancountFile for Inventory {
+    fn to_text(&self) -> String {
+        self.positions().iter().map(|p| p.to_text()).join(", ")
+    }
+}
+
 impl ToBeancountFile for crate::models::Flag {
     fn to_text(&self) -> String {
         match self {
             Flag::Complete => "*".to_owned(),
             Flag::Incomplete => "!".to_owned(),
+            Flag::Padding => "P".to_owned(),
+            Flag::Summarize => "S".to_owned(),
+            Flag::Transfer => "T".to_owned(),
+            Flag::Conversion => "C".to_owned(),
+            Flag::Unrealized => "U".to_owned(),
+            Flag::Forecast => "#".to_owned(),
         }
     }
 }
@@ -33,7 +66,8 @@ impl ToBeancountFile for crate::models::TransactionLine {
     fn to_text(&self) -> String {
         let mut builder = String::new();
         if self.flag != Flag::Complete {
-            builder.push_str("! ");
+            builder.push_str(&self.flag.to_text());
+            builder.push(' ');
         }
         builder.push_str(&self.account.to_string());
         if let Some(amount_inner) = &self.amount {
@@ -54,7 +88,7 @@ impl ToBeancountFile for crate::models::TransactionLine {
         if let Some(inner) = &self.total_price {
             builder.push_str(&format!(" @@ {}", inner.to_text()));
         };
-        builder
+        with_comment(builder, &self.comment)
     }
 }
 
@@ -87,6 +121,7 @@ impl ToBeancountFile for crate::models::Transaction {
             .map(|inner| format!(" ^{}", inner))
             .join("");
         builder.push_str(&links);
+        let mut builder = with_comment(builder, &self.comment);
 
         let lines = self
             .lines
@@ -106,6 +141,7 @@ impl ToBeancountFile for crate::models::Directive {
                 date,
                 account,
                 commodities,
+                comment,
             } => {
                 let mut string = format!(
                     "{date} open {account}",
@@ -116,115 +152,190 @@ impl ToBeancountFile for crate::models::Directive {
                     string.push(' ');
                     string.push_str(&commodities_data.iter().join(", "));
                 };
-                string
+                with_comment(string, comment)
             }
 
-            Directive::Close { date, account } => format!(
-                "{date} close {account}",
-                date = &date.to_string(),
-                account = &account.to_string()
+            Directive::Close {
+                date,
+                account,
+                comment,
+            } => with_comment(
+                format!(
+                    "{date} close {account}",
+                    date = &date.to_string(),
+                    account = &account.to_string()
+                ),
+                comment,
             ),
-            Directive::Commodity { date, name, metas } => {
+            Directive::Commodity {
+                date,
+                name,
+                metas,
+                comment,
+            } => {
                 let meta_info = metas
                     .iter()
                     .map(|(key, value)| {
                         format!("\n  {}: {}", key.clone(), escape_with_quote(value))
                     })
                     .join("");
-                format!(
-                    "{date} commodity {name}{meta_info}",
+                let header = format!(
+                    "{date} commodity {name}",
                     date = &date.to_string(),
-                    name = name,
-                    meta_info = meta_info
-                )
+                    name = name
+                );
+                format!("{}{}", with_comment(header, comment), meta_info)
             }
             Directive::Transaction(model) => model.to_text(),
             Directive::Balance {
                 date,
                 account,
                 amount,
-            } => format!(
-                "{date} balance {account} {amount}",
-                date = date.to_string(),
-                account = account.to_string(),
-                amount = amount.to_text()
+                comment,
+            } => with_comment(
+                format!(
+                    "{date} balance {account} {amount}",
+                    date = date.to_string(),
+                    account = account.to_string(),
+                    amount = amount.to_text()
+                ),
+                comment,
             ),
-            Directive::Pad { date, from, to } => format!(
-                "{date} pad {from} {to}",
-                date = date.to_string(),
-                from = from.to_string(),
-                to = to.to_string()
+            Directive::Pad {
+                date,
+                from,
+                to,
+                comment,
+            } => with_comment(
+                format!(
+                    "{date} pad {from} {to}",
+                    date = date.to_string(),
+                    from = from.to_string(),
+                    to = to.to_string()
+                ),
+                comment,
             ),
             Directive::Note {
                 date,
                 account,
                 description,
-            } => format!(
-                "{date} note {account} {description}",
-                date = date.to_string(),
-                account = account.to_string(),
-                description = escape_with_quote(description)
+                comment,
+            } => with_comment(
+                format!(
+                    "{date} note {account} {description}",
+                    date = date.to_string(),
+                    account = account.to_string(),
+                    description = escape_with_quote(description)
+                ),
+                comment,
             ),
             Directive::Document {
                 date,
                 account,
                 path,
-            } => format!(
-                "{date} document {account} {path}",
-                date = date.to_string(),
-                account = account.to_string(),
-                path = escape_with_quote(path)
+                comment,
+            } => with_comment(
+                format!(
+                    "{date} document {account} {path}",
+                    date = date.to_string(),
+                    account = account.to_string(),
+                    path = escape_with_quote(path)
+                ),
+                comment,
             ),
             Directive::Price {
                 date,
                 commodity,
                 amount,
-            } => format!(
-                "{date} price {commodity} {amount}",
-                date = date.to_string(),
-                commodity = commodity,
-                amount = amount.to_text()
+                comment,
+            } => with_comment(
+                format!(
+                    "{date} price {commodity} {amount}",
+                    date = date.to_string(),
+                    commodity = commodity,
+                    amount = amount.to_text()
+                ),
+                comment,
             ),
-            Directive::Event { date, name, value } => format!(
-                "{date} event {name} {value}",
-                date = date.to_string(),
-                name = escape_with_quote(name),
-                value = escape_with_quote(value),
+            Directive::Event {
+                date,
+                name,
+                value,
+                comment,
+            } => with_comment(
+                format!(
+                    "{date} event {name} {value}",
+                    date = date.to_string(),
+                    name = escape_with_quote(name),
+                    value = escape_with_quote(value),
+                ),
+                comment,
             ),
             Directive::Custom {
                 date,
                 type_name,
                 values,
-            } => format!(
-                "{date} custom {type_name} {value}",
-                date = date.to_string(),
-                type_name = escape_with_quote(type_name),
-                value = values.iter().map(|v| escape_with_quote(v)).join(" ")
+                comment,
+            } => with_comment(
+                format!(
+                    "{date} custom {type_name} {value}",
+                    date = date.to_string(),
+                    type_name = escape_with_quote(type_name),
+                    value = values.iter().map(|v| v.to_text()).join(" ")
+                ),
+                comment,
             ),
-            Directive::Option { key, value } => format!(
-                "option {} {}",
-                escape_with_quote(key),
-                escape_with_quote(value)
+            Directive::Option {
+                key,
+                value,
+                comment,
+            } => with_comment(
+                format!(
+                    "option {} {}",
+                    escape_with_quote(key),
+                    escape_with_quote(value)
+                ),
+                comment,
             ),
-            Directive::Plugin { module, value } => {
+            Directive::Plugin {
+                module,
+                value,
+                comment,
+            } => {
                 let mut builder = format!("plugin {}", escape_with_quote(module),);
                 if let Some(inner) = value {
                     builder.push_str(&format!(" {}", escape_with_quote(inner)));
                 }
-                builder
+                with_comment(builder, comment)
+            }
+            Directive::Include { file, comment } => {
+                with_comment(format!("include {}", escape_with_quote(file)), comment)
             }
-            Directive::Include { file } => format!("include {}", escape_with_quote(file)),
             Directive::Comment { content } => content.to_owned(),
         }
     }
 }
 
+/// append trailing `; comment` to a line
+fn with_comment(line: String, comment: &Option<String>) -> String {
+    match comment {
+        Some(comment) => format!("{} ; {}", line, comment),
+        None => line,
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::{models::Directive, parser::DirectiveExpressionParser, to_file::ToBeancountFile};
+    use crate::{
+        models::{AccountNames, Directive},
+        parser::DirectiveExpressionParser,
+        to_file::ToBeancountFile,
+    };
 
     fn parse(from: &str) -> String {
-        let direct: Directive = DirectiveExpressionParser::new().parse(from).unwrap();
+        let direct: Directive = DirectiveExpressionParser::new()
+            .parse(&AccountNames::default(), from)
+            .unwrap();
         direct.to_text()
     }
 
@@ -323,6 +434,24 @@ mod test {
                   Expenses:TestCategory:One 1 CCC @@ 1 CNY"#)
         );
     }
+
+    #[test]
+    fn synthetic_flags() {
+        for flag in ["P", "S", "T", "C", "U"] {
+            parse_and_test(&format!(
+                "1970-01-01 {} \"Narration\"\n  Assets:123 -1 CNY\n  {} Equity:ABC 1 CNY",
+                flag, flag
+            ));
+        }
+    }
+
+    #[test]
+    fn single_letter_tags_and_links() {
+        parse_and_test(
+            "1970-01-01 * \"Narration\" #T #C ^P ^C\n  Assets:123 -1 CNY\n  T Equity:ABC 1 CNY",
+        );
+    }
+
     #[test]
     fn pad() {
         parse_and_test("1970-01-01 pad Assets:123:234:English:中文:日本語:한국어 Equity:ABC");
@@ -347,9 +476,10 @@ mod test {
         parse_and_test(r#"1970-01-01 event "location" "China""#);
     }
     #[test]
-    #[ignore]
     fn custom() {
         parse_and_test(r#"1970-01-01 custom "budget" Expenses:Eat "monthly" CNY"#);
+        parse_and_test(r#"1970-01-01 custom "budget" Expenses:Eat "monthly" 100 CNY"#);
+        parse_and_test(r#"1970-01-01 custom "budget" "Expenses:Eat" TRUE"#);
     }
 
     #[test]
@@ -365,4 +495,18 @@ mod test {
     fn comment() {
         parse_and_test(";你好啊");
     }
+
+    #[test]
+    fn inline_comment() {
+        parse_and_test("1970-01-01 open Assets:Bank CNY ; main account");
+        parse_and_test("1970-01-01 commodity CNY ; yuan\n  a: \"b\"");
+        parse_and_test("1970-01-01 balance Assets:Bank 10 CNY ; checked");
+        parse_and_test("option \"title\" \"Home\" ; title");
+        assert_eq!(
+            "1970-01-01 * \"Lunch\" #food ; paid by card\n  Assets:Bank -1 CNY ; card\n  Expenses:Food ; rice",
+            parse(r#"1970-01-01 * "Lunch" #food  ;paid by card
+                  Assets:Bank  -1 CNY   ; card
+                  Expenses:Food	; rice"#)
+        );
+    }
 }